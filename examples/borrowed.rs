@@ -0,0 +1,89 @@
+use std::error::Error;
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize, Serializer};
+use serde::de::DeserializeSeed;
+
+use serde_flexitos::borrow::{BorrowMapRegistry, BorrowRegistry, DeserializeBorrowTraitObject, TraitObjectFamily};
+use serde_flexitos::de::DeserializeSeqWith;
+use serde_flexitos::serialize_trait_object;
+
+// Example trait, implemented by log events that borrow from the log
+
+pub trait LogEvent: erased_serde::Serialize + Debug {
+  fn id(&self) -> &'static str;
+  fn message(&self) -> &str;
+}
+
+// Family of `LogEvent` trait objects borrowing from input with lifetime `'de`.
+
+struct LogEventFamily;
+impl TraitObjectFamily for LogEventFamily {
+  type TraitObject<'de> = dyn LogEvent + 'de;
+}
+
+// Example trait implementations, borrowing strings from the input
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Request<'a> {
+  method: &'a str,
+  path: &'a str,
+}
+impl Request<'_> {
+  const ID: &'static str = "Request";
+}
+impl LogEvent for Request<'_> {
+  fn id(&self) -> &'static str { Self::ID }
+  fn message(&self) -> &str { self.path }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Warning<'a>(&'a str);
+impl Warning<'_> {
+  const ID: &'static str = "Warning";
+}
+impl LogEvent for Warning<'_> {
+  fn id(&self) -> &'static str { Self::ID }
+  fn message(&self) -> &str { self.0 }
+}
+
+// Serialize implementation
+
+impl Serialize for dyn LogEvent + '_ {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object(serializer, self.id(), self)
+  }
+}
+
+// Deserialize events that borrow from the log
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let mut registry = BorrowMapRegistry::<LogEventFamily>::new("LogEvent");
+  registry.register(Request::ID, |d| Ok(Box::new(erased_serde::deserialize::<Request>(d)?)));
+  registry.register(Warning::ID, |d| Ok(Box::new(erased_serde::deserialize::<Warning>(d)?)));
+
+  { // Single event, borrowing from `json`
+    let json = r#"{"Warning":"disk almost full"}"#.to_string();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let event = registry.deserialize_trait_object(&mut deserializer)?;
+    println!("`Box<dyn LogEvent>` deserialized: {:?} with message {}", event, event.message());
+  }
+
+  { // Sequence of events, borrowing from `log`
+    let log = r#"[{"Request":{"method":"GET","path":"/index.html"}},{"Warning":"slow response"}]"#.to_string();
+    let deserialize = DeserializeSeqWith::new(DeserializeBorrowTraitObject(&registry));
+    let mut deserializer = serde_json::Deserializer::from_str(&log);
+    let events: Vec<Box<dyn LogEvent>> = deserialize.deserialize(&mut deserializer)?;
+    println!("`Vec<Box<dyn LogEvent>>` deserialized: {:?}", events);
+    let json = serde_json::to_string(&events)?;
+    println!("`Vec<Box<dyn LogEvent>>`   serialized: {}", json);
+  }
+
+  // Borrowed strings cannot contain escape sequences, so deserializing those fails. Use `Cow<'a, str>` with
+  // `#[serde(borrow)]` to borrow when possible and allocate otherwise.
+  let json = r#"{"Warning":"quoted \"path\""}"#;
+  let error = registry.deserialize_trait_object(&mut serde_json::Deserializer::from_str(json)).unwrap_err();
+  println!("`Box<dyn LogEvent>` with escape sequences: {}", error);
+
+  Ok(())
+}
@@ -0,0 +1,95 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Debug;
+
+use serde::de::DeserializeSeed;
+use serde::Deserialize;
+
+use serde_flexitos::context::{ContextMapRegistry, ContextRegistry, DeserializeVecWithTraitObjectWith};
+use serde_flexitos::de::DeserializeMapWith;
+
+// Example context: a world of entities, where entities are referenced by name in serialized data, and by index at
+// runtime.
+
+#[derive(Default, Debug)]
+pub struct World {
+  entities: Vec<String>,
+}
+impl World {
+  fn resolve(&mut self, name: String) -> usize {
+    if let Some(index) = self.entities.iter().position(|e| *e == name) {
+      index
+    } else {
+      self.entities.push(name);
+      self.entities.len() - 1
+    }
+  }
+}
+
+// Example trait
+
+pub trait Component: Debug {
+  fn describe(&self, world: &World) -> String;
+}
+
+// Example trait implementations
+
+#[derive(Debug)]
+struct Follow {
+  target: usize,
+}
+impl Follow {
+  const ID: &'static str = "Follow";
+}
+impl Component for Follow {
+  fn describe(&self, world: &World) -> String { format!("follows {}", world.entities[self.target]) }
+}
+
+#[derive(Deserialize, Debug)]
+struct Health(u32);
+impl Health {
+  const ID: &'static str = "Health";
+}
+impl Component for Health {
+  fn describe(&self, _world: &World) -> String { format!("has {} health", self.0) }
+}
+
+// Run deserialization
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let mut registry = ContextMapRegistry::<dyn Component, World>::new("Component");
+  registry.register(Follow::ID, |d, world| {
+    // Resolve the serialized entity name to an entity index using the world.
+    let target = erased_serde::deserialize::<String>(d)?;
+    Ok(Box::new(Follow { target: world.resolve(target) }))
+  });
+  registry.register(Health::ID, |d, _| Ok(Box::new(erased_serde::deserialize::<Health>(d)?)));
+
+  // Share the world in a `RefCell` to deserialize collections, as each element mutably borrows it.
+  let world = RefCell::new(World::default());
+  let json = r#"[{"Follow":"player"},{"Health":10},{"Follow":"enemy"},{"Follow":"player"}]"#;
+  let deserialize = DeserializeVecWithTraitObjectWith { registry: &registry, context: &world };
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let components = deserialize.deserialize(&mut deserializer)?;
+  println!("`Vec<Box<dyn Component>>` deserialized: {:?} from {}", components, json);
+
+  let json = r#"{"scout":{"Follow":"enemy"},"tank":{"Health":100}}"#;
+  let deserialize = DeserializeMapWith::trait_object_value_with(&registry, &world);
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let named: HashMap<String, Box<dyn Component>> = deserialize.deserialize(&mut deserializer)?;
+  println!("`HashMap<String, Box<dyn Component>>` deserialized: {:?} from {}", named, json);
+
+  let mut world = world.into_inner();
+
+  let json = r#"{"Follow":"enemy"}"#;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let component = registry.deserialize_trait_object_with(&mut world, &mut deserializer)?;
+  println!("`Box<dyn Component>` deserialized: {:?} from {}", component, json);
+  println!("World: {:?}", world);
+  for component in components.iter().chain(named.values()).chain(Some(&component)) {
+    println!("Component {}", component.describe(&world));
+  }
+
+  Ok(())
+}
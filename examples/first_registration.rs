@@ -2,7 +2,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_flexitos::ser::require_erased_serialize_impl;
 #[allow(unused_imports)]
 use serde_flexitos::MapRegistry;
-use serde_flexitos::{serialize_trait_object, DeserializeFn, GetError, Registry};
+use serde_flexitos::{serialize_trait_object, DynDeserializeFn, GetError, Registry};
 use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt::Debug;
@@ -24,7 +24,7 @@ use std::sync::LazyLock;
 /// there is no guarantee about the order in which registrations are performed. This could lead to subtle bugs where
 /// changing an unrelated part of the program changes the deserialization function!
 pub struct FirstMapRegistry<O: ?Sized, I = &'static str> {
-  deserialize_fns: BTreeMap<I, DeserializeFn<O>>,
+  deserialize_fns: BTreeMap<I, Box<DynDeserializeFn<O>>>,
   trait_object_name: &'static str,
 }
 
@@ -39,18 +39,21 @@ impl<O: ?Sized, I> FirstMapRegistry<O, I> {
   }
 }
 
-impl<O: ?Sized, I: Ord> Registry for FirstMapRegistry<O, I> {
+impl<O: ?Sized + 'static, I: Ord> Registry for FirstMapRegistry<O, I> {
   type Identifier = I;
   type TraitObject = O;
 
   #[inline]
-  fn register(&mut self, id: Self::Identifier, deserialize_fn: DeserializeFn<Self::TraitObject>) {
+  fn register_boxed(&mut self, id: Self::Identifier, deserialize_fn: Box<DynDeserializeFn<Self::TraitObject>>) {
     self.deserialize_fns.entry(id).or_insert(deserialize_fn);
   }
 
   #[inline]
-  fn get_deserialize_fn(&self, id: Self::Identifier) -> Result<&DeserializeFn<Self::TraitObject>, GetError<Self::Identifier>> {
-    self.deserialize_fns.get(&id).ok_or_else(|| GetError::NotRegistered { id })
+  fn get_deserialize_fn(
+    &self,
+    id: Self::Identifier,
+  ) -> Result<&DynDeserializeFn<Self::TraitObject>, GetError<Self::Identifier>> {
+    self.deserialize_fns.get(&id).map(Box::as_ref).ok_or_else(|| GetError::NotRegistered { id })
   }
 
   #[inline]
@@ -30,10 +30,10 @@ impl ExampleObj<usize> for Foo {
   fn key(&self) -> &'static str { Self::USIZE_KEY }
   fn get(&self) -> usize { self.0.len() }
 }
-impl<T> Into<Box<dyn ExampleObj<T>>> for Foo where
+impl<T> From<Foo> for Box<dyn ExampleObj<T>> where
   Foo: ExampleObj<T>,
 {
-  fn into(self) -> Box<dyn ExampleObj<T>> { Box::new(self) }
+  fn from(value: Foo) -> Self { Box::new(value) }
 }
 
 // Actually stores something of the generic type.
@@ -53,10 +53,10 @@ impl ExampleObj<usize> for Bar<usize> {
   fn key(&self) -> &'static str { Self::KEY }
   fn get(&self) -> usize { self.0 }
 }
-impl<T: 'static> Into<Box<dyn ExampleObj<T>>> for Bar<T> where
+impl<T: 'static> From<Bar<T>> for Box<dyn ExampleObj<T>> where
   Bar<T>: ExampleObj<T>,
 {
-  fn into(self) -> Box<dyn ExampleObj<T>> { Box::new(self) }
+  fn from(value: Bar<T>) -> Self { Box::new(value) }
 }
 
 // Registries
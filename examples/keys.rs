@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::DeserializeSeed;
+
+use serde_flexitos::{MapRegistry, Registry, serialize_trait_object_key};
+use serde_flexitos::de::{DeserializeMapWith, DeserializeTraitObjectKey};
+use serde_flexitos::key::JsonKeyCodec;
+
+// Example trait, used as map key
+
+pub trait ExampleKey: erased_serde::Serialize + Debug {
+  fn id(&self) -> &'static str;
+  fn name(&self) -> &str;
+}
+
+// Example trait implementations
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Foo(String);
+impl Foo {
+  const ID: &'static str = "Foo";
+}
+impl ExampleKey for Foo {
+  fn id(&self) -> &'static str { Self::ID }
+  fn name(&self) -> &str { &self.0 }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Bar {
+  name: String,
+  priority: usize,
+}
+impl Bar {
+  const ID: &'static str = "Bar";
+}
+impl ExampleKey for Bar {
+  fn id(&self) -> &'static str { Self::ID }
+  fn name(&self) -> &str { &self.name }
+}
+
+// Equality and hashing of trait objects, required for `HashMap` keys.
+
+impl PartialEq for dyn ExampleKey {
+  fn eq(&self, other: &Self) -> bool { self.id() == other.id() && self.name() == other.name() }
+}
+impl Eq for dyn ExampleKey {}
+impl Hash for dyn ExampleKey {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.id().hash(state);
+    self.name().hash(state);
+  }
+}
+
+// Registry
+
+static EXAMPLE_KEY_REGISTRY: LazyLock<MapRegistry<dyn ExampleKey>> = LazyLock::new(|| {
+  let mut registry = MapRegistry::<dyn ExampleKey>::new("ExampleKey");
+  registry.register_type::<Foo>(Foo::ID);
+  registry.register_type::<Bar>(Bar::ID);
+  registry
+});
+
+// (De)serialize implementations, using the string key representation, as JSON only supports string keys.
+
+impl Serialize for dyn ExampleKey {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object_key(serializer, self.id(), self, JsonKeyCodec)
+  }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn ExampleKey> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    EXAMPLE_KEY_REGISTRY.deserialize_trait_object_key(JsonKeyCodec, deserializer)
+  }
+}
+
+// Run serialization roundtrip
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let mut examples = HashMap::<Box<dyn ExampleKey>, usize>::new();
+  examples.insert(Box::new(Foo("A".to_string())), 1);
+  examples.insert(Box::new(Bar { name: "B".to_string(), priority: 2 }), 2);
+  let json = serde_json::to_string(&examples)?;
+  println!("`HashMap<Box<dyn ExampleKey>, usize>`   serialized: {}", json);
+
+  let roundtrip: HashMap<Box<dyn ExampleKey>, usize> = serde_json::from_str(&json)?;
+  println!("`HashMap<Box<dyn ExampleKey>, usize>` deserialized: {:?}", roundtrip);
+
+  // Or use `DeserializeTraitObjectKey` with a local registry instead of the `Deserialize` impl.
+  let key_deserialize = DeserializeTraitObjectKey { registry: &*EXAMPLE_KEY_REGISTRY, codec: JsonKeyCodec };
+  let deserialize = DeserializeMapWith::new(key_deserialize, PhantomData);
+  let mut deserializer = serde_json::Deserializer::from_str(&json);
+  let roundtrip: HashMap<Box<dyn ExampleKey>, usize> = deserialize.deserialize(&mut deserializer)?;
+  println!("`HashMap<Box<dyn ExampleKey>, usize>` deserialized with seed: {:?}", roundtrip);
+
+  Ok(())
+}
+
+// Conversions into boxed trait objects, required by `Registry::register_type`.
+
+impl From<Foo> for Box<dyn ExampleKey> {
+  fn from(value: Foo) -> Self { Box::new(value) }
+}
+impl From<Bar> for Box<dyn ExampleKey> {
+  fn from(value: Bar) -> Self { Box::new(value) }
+}
@@ -21,14 +21,17 @@ macro_rules! create_registry {
   };
   ($trait_object:ident, $register_macro:ident, $ident:ty, $($type_to_ident:ident)::*, $registry:ident, $distributed_slice:ident) => {
     #[linkme::distributed_slice]
-    pub static $distributed_slice: [fn(&mut serde_flexitos::MapRegistry<dyn $trait_object, $ident>)] = [..];
+    pub static $distributed_slice: [fn(&mut serde_flexitos::builder::RegistryBuilder<dyn $trait_object, $ident>)] =
+      [..];
 
     static $registry: std::sync::LazyLock<serde_flexitos::MapRegistry<dyn $trait_object, $ident>> = std::sync::LazyLock::new(|| {
-      let mut registry = serde_flexitos::MapRegistry::<dyn $trait_object, $ident>::new(stringify!($trait_object));
+      let mut builder =
+        serde_flexitos::builder::RegistryBuilder::<dyn $trait_object, $ident>::new(stringify!($trait_object));
       for registry_fn in $distributed_slice {
-        registry_fn(&mut registry);
+        registry_fn(&mut builder);
       }
-      registry
+      // Fail at first use of the registry, listing all duplicate IDs, instead of at deserialization-time.
+      builder.build().unwrap_or_else(|e| panic!("{}", e))
     });
 
     impl<'a> serde::Serialize for dyn $trait_object + 'a {
@@ -55,17 +58,19 @@ macro_rules! create_registry {
         impl serde_flexitos::id::Id<$ident> for $generic<$arg> {
           const ID: $ident = $($type_to_ident)::*!($generic<$arg>);
         }
-        impl Into<Box<dyn $trait_object>> for $generic<$arg> where {
+        impl From<$generic<$arg>> for Box<dyn $trait_object> {
           #[inline]
-          fn into(self) -> Box<dyn $trait_object> {
-            Box::new(self)
+          fn from(value: $generic<$arg>) -> Self {
+            Box::new(value)
           }
         }
 
         paste::paste! {
           #[linkme::distributed_slice($distributed_slice)]
           #[inline]
-          fn [< __register_ $generic:snake _ $arg:snake >](registry: &mut serde_flexitos::MapRegistry<dyn $trait_object, $ident>) {
+          fn [< __register_ $generic:snake _ $arg:snake >](
+            registry: &mut serde_flexitos::builder::RegistryBuilder<dyn $trait_object, $ident>,
+          ) {
             use serde_flexitos::Registry;
             registry.register_id_type::<$generic<$arg>>();
           }
@@ -75,17 +80,19 @@ macro_rules! create_registry {
         impl serde_flexitos::id::Id<$ident> for $concrete {
           const ID: $ident = $($type_to_ident)::*!($concrete);
         }
-        impl Into<Box<dyn $trait_object>> for $concrete where {
+        impl From<$concrete> for Box<dyn $trait_object> {
           #[inline]
-          fn into(self) -> Box<dyn $trait_object> {
-            Box::new(self)
+          fn from(value: $concrete) -> Self {
+            Box::new(value)
           }
         }
 
         paste::paste! {
           #[linkme::distributed_slice($distributed_slice)]
           #[inline]
-          fn [< __register_ $concrete:snake >](registry: &mut serde_flexitos::MapRegistry<dyn $trait_object, $ident>) {
+          fn [< __register_ $concrete:snake >](
+            registry: &mut serde_flexitos::builder::RegistryBuilder<dyn $trait_object, $ident>,
+          ) {
             use serde_flexitos::Registry;
             registry.register_id_type::<$concrete>();
           }
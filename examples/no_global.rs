@@ -1,12 +1,19 @@
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::error::Error;
 use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
-use serde::{Deserialize, Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::DeserializeSeed;
 
 use serde_flexitos::{MapRegistry, Registry, serialize_trait_object};
-use serde_flexitos::de::{DeserializeMapWith, DeserializeTraitObject, DeserializeVecWithTraitObject};
+use serde_flexitos::de::{
+  DeserializeArrayWith, DeserializeMapWith, DeserializeOptionWith, DeserializeSeqWith, DeserializeTraitObject,
+  DeserializeTupleWith, DeserializeVecWithTraitObject, DeserializeVecWithTraitObjectCollectErrors,
+};
 use serde_flexitos::ser::require_erased_serialize_impl;
 
 // Example trait
@@ -46,6 +53,23 @@ impl<'a> Serialize for dyn ExampleObj + 'a {
   }
 }
 
+// Seed that counts the trait objects it deserializes, which is `Clone` but not `Copy` due to its shared state.
+
+#[derive(Clone)]
+struct CountingDeserializeTraitObject<'r> {
+  registry: &'r MapRegistry<dyn ExampleObj>,
+  count: Rc<Cell<usize>>,
+}
+
+impl<'de> DeserializeSeed<'de> for CountingDeserializeTraitObject<'_> {
+  type Value = Box<dyn ExampleObj>;
+
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    self.count.set(self.count.get() + 1);
+    DeserializeTraitObject(self.registry).deserialize(deserializer)
+  }
+}
+
 // Run serialization roundtrips
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -53,6 +77,13 @@ fn main() -> Result<(), Box<dyn Error>> {
   registry.register(Foo::ID, |d| Ok(Box::new(erased_serde::deserialize::<Foo>(d)?)));
   registry.register(Bar::ID, |d| Ok(Box::new(erased_serde::deserialize::<Bar>(d)?)));
 
+  // Inspect the registry, failing at startup if any ID was registered multiple times.
+  let ids: Vec<_> = registry.get_ids().collect();
+  println!("Registry of {} types: {:?}, contains `Foo`: {}", registry.len(), ids, registry.contains(&Foo::ID));
+  for id in registry.get_ids_with_multiple_registrations() {
+    registry.get_deserialize_fn(*id)?; // Fails with an error listing the types and locations of the registrations.
+  }
+
   let foo = Foo("A".to_string());
   let bar = Bar(0);
 
@@ -67,6 +98,16 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("`Box<dyn ExampleObj>` deserialized: {:?}", roundtrip);
   }
 
+  { // `Arc<dyn ExampleObj>` serialization roundtrip, without converting from `Box<dyn ExampleObj>` manually
+    let example: Arc<dyn ExampleObj> = Arc::new(foo.clone());
+    let json = serde_json::to_string(example.as_ref())?;
+    println!("`Arc<dyn ExampleObj>`   serialized: {}", json);
+
+    let mut deserializer = serde_json::Deserializer::new(serde_json::de::StrRead::new(&json));
+    let roundtrip: Arc<dyn ExampleObj> = registry.deserialize_trait_object_arc(&mut deserializer)?;
+    println!("`Arc<dyn ExampleObj>` deserialized: {:?}", roundtrip);
+  }
+
   { // `Vec<Box<dyn ExampleObj>>` serialization roundtrip
     let examples: Vec<Box<dyn ExampleObj>> = vec![Box::new(foo.clone()), Box::new(bar.clone())];
     let json = serde_json::to_string(&examples)?;
@@ -78,6 +119,17 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("`Vec<Box<dyn ExampleObj>>` deserialized: {:?}", roundtrip);
   }
 
+  { // `Vec<Box<dyn ExampleObj>>` deserialization, collecting errors of all failing elements
+    let json = r#"[{"Foo":"A"},{"Bar":"not a number"},{"Baz":0},{"Bar":1}]"#;
+    let deserialize = DeserializeVecWithTraitObjectCollectErrors(&registry);
+    let mut deserializer = serde_json::Deserializer::new(serde_json::de::StrRead::new(json));
+    let collected = deserialize.deserialize(&mut deserializer)?;
+    println!("`Vec<Box<dyn ExampleObj>>` deserialized: {:?} from {}", collected.value, json);
+    for error in &collected.errors {
+      println!("  error in {}", error);
+    }
+  }
+
   { // `HashMap<String, Box<dyn ExampleObj>>` serialization roundtrip
     let mut examples = HashMap::<String, Box<dyn ExampleObj>>::new();
     examples.insert("foo".to_string(), Box::new(foo.clone()));
@@ -91,12 +143,77 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("`HashMap<String, Box<dyn ExampleObj>>` deserialized: {:?}", roundtrip);
   }
 
-  // This example uses `DeserializeTraitObject`, `DeserializeVecWithTraitObject`, and `DeserializeMapWith`, which
-  // implement `DeserializeSeed` instead of `Deserialize`.
+  { // Other collections of trait objects, composed from seeds
+    let json = r#"{"b":{"Bar":0},"a":{"Foo":"A"}}"#;
+    let deserialize = DeserializeMapWith::trait_object_value(&registry);
+    let mut deserializer = serde_json::Deserializer::new(serde_json::de::StrRead::new(json));
+    let map: BTreeMap<String, Box<dyn ExampleObj>> = deserialize.deserialize(&mut deserializer)?;
+    println!("`BTreeMap<String, Box<dyn ExampleObj>>` deserialized: {:?}", map);
+
+    let json = r#"[[{"Foo":"A"}],[{"Bar":0},{"Bar":1}]]"#;
+    let deserialize = DeserializeSeqWith::new(DeserializeSeqWith::trait_object(&registry));
+    let mut deserializer = serde_json::Deserializer::new(serde_json::de::StrRead::new(json));
+    let nested: Vec<VecDeque<Box<dyn ExampleObj>>> = deserialize.deserialize(&mut deserializer)?;
+    println!("`Vec<VecDeque<Box<dyn ExampleObj>>>` deserialized: {:?}", nested);
+
+    let json = r#"[{"Foo":"A"},null]"#;
+    let deserialize = DeserializeArrayWith(DeserializeOptionWith(DeserializeTraitObject(&registry)));
+    let mut deserializer = serde_json::Deserializer::new(serde_json::de::StrRead::new(json));
+    let array: [Option<Box<dyn ExampleObj>>; 2] = deserialize.deserialize(&mut deserializer)?;
+    println!("`[Option<Box<dyn ExampleObj>>; 2]` deserialized: {:?}", array);
+
+    let json = r#"[{"Bar":0}]"#;
+    let deserialize = DeserializeSeqWith::trait_object(&registry);
+    let mut deserializer = serde_json::Deserializer::new(serde_json::de::StrRead::new(json));
+    let slice: Box<[Box<dyn ExampleObj>]> = deserialize.deserialize(&mut deserializer)?;
+    println!("`Box<[Box<dyn ExampleObj>]>` deserialized: {:?}", slice);
+  }
+
+  { // Stateful seeds that are `Clone` but not `Copy`, nested in seeds for collections and tuples
+    let count = Rc::new(Cell::new(0));
+    let counting = CountingDeserializeTraitObject { registry: &registry, count: count.clone() };
+    let json = r#"[[{"Foo":"A"},{"Bar":0}],{"b":{"Bar":1}},null]"#;
+    let deserialize = DeserializeTupleWith((
+      DeserializeSeqWith::new(counting.clone()),
+      DeserializeMapWith::new(PhantomData, counting.clone()),
+      DeserializeOptionWith(counting),
+    ));
+    let mut deserializer = serde_json::Deserializer::new(serde_json::de::StrRead::new(json));
+    let (vec, map, option): (Vec<_>, BTreeMap<String, _>, Option<_>) = deserialize.deserialize(&mut deserializer)?;
+    println!(
+      "`(Vec<..>, BTreeMap<..>, Option<..>)` deserialized: ({:?}, {:?}, {:?}), counting {} trait objects",
+      vec, map, option, count.get()
+    );
+  }
+
+  { // Deserialize functions that capture state, such as a cache or an interner
+    let names = Arc::new(Mutex::new(Vec::<String>::new()));
+    let mut registry = MapRegistry::<dyn ExampleObj>::new("ExampleObj");
+    let interned = names.clone();
+    registry.register_closure(Foo::ID, move |d| {
+      let foo = erased_serde::deserialize::<Foo>(d)?;
+      interned.lock().unwrap().push(foo.0.clone());
+      Ok(Box::new(foo))
+    });
+    registry.register(Bar::ID, |d| Ok(Box::new(erased_serde::deserialize::<Bar>(d)?)));
+
+    let json = r#"[{"Foo":"A"},{"Bar":0},{"Foo":"B"}]"#;
+    let deserialize = DeserializeVecWithTraitObject(&registry);
+    let mut deserializer = serde_json::Deserializer::new(serde_json::de::StrRead::new(json));
+    let deserialized: Vec<Box<dyn ExampleObj>> = deserialize.deserialize(&mut deserializer)?;
+    let names = names.lock().unwrap();
+    println!("`Vec<Box<dyn ExampleObj>>` deserialized: {:?}, collecting names {:?}", deserialized, names);
+  }
+
+  // This example uses `DeserializeTraitObject`, `DeserializeVecWithTraitObject`, `DeserializeMapWith`, and other seeds
+  // in the `de` module, which implement `DeserializeSeed` instead of `Deserialize`.
   //
   // If you need to deserialize trait objects inside your custom data structures, this will require a lot of extra
   // boilerplate, due to `serde_derive` not deriving `DeserializeSeed` implementations. See
   // https://stackoverflow.com/a/75902605 for an example on how to write these implementations.
+  //
+  // Alternatively, install a local registry in a thread-local scope and look it up in `Deserialize` implementations, as
+  // is done in `examples/scoped.rs`.
 
   Ok(())
 }
@@ -0,0 +1,192 @@
+use std::error::Error;
+use std::fmt::Debug;
+
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+
+use serde::de::DeserializeSeed;
+
+use serde_flexitos::{MapRegistry, Registry, serialize_trait_object_untagged, serialize_trait_object_variant};
+use serde_flexitos::de::DeserializeTraitObjectUntagged;
+use serde_flexitos::ser::{
+  SerializeTraitObjectAdjacentlyTagged, SerializeTraitObjectCompact, SerializeTraitObjectInternallyTagged,
+  SerializeTraitObjectTuple,
+};
+
+// Example trait
+
+pub trait ExampleObj: erased_serde::Serialize + Debug {
+  fn id(&self) -> &'static str;
+}
+
+// Example trait implementations
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Foo {
+  name: String,
+}
+impl Foo {
+  const ID: &'static str = "Foo";
+}
+impl ExampleObj for Foo {
+  fn id(&self) -> &'static str { Self::ID }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Bar {
+  count: usize,
+}
+impl Bar {
+  const ID: &'static str = "Bar";
+}
+impl ExampleObj for Bar {
+  fn id(&self) -> &'static str { Self::ID }
+}
+
+// Run serialization roundtrips
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let mut registry = MapRegistry::<dyn ExampleObj>::new("ExampleObj");
+  registry.register_type::<Foo>(Foo::ID);
+  registry.register_type::<Bar>(Bar::ID);
+  // Variants of the enum variant representation. Like Rust enum variants, only append to keep serialized data readable.
+  registry.set_variants(&[Foo::ID, Bar::ID]);
+
+  let examples: Vec<Box<dyn ExampleObj>> = vec![
+    Box::new(Foo { name: "A".to_string() }),
+    Box::new(Bar { count: 0 }),
+  ];
+
+  { // Internally tagged: `{"type":"Foo","name":"A"}`
+    for example in &examples {
+      let serialize =
+        SerializeTraitObjectInternallyTagged { tag: "type", id: example.id(), trait_object: example.as_ref() };
+      let json = serde_json::to_string(&serialize)?;
+      println!("Internally tagged   serialized: {}", json);
+
+      let mut deserializer = serde_json::Deserializer::from_str(&json);
+      let roundtrip = registry.deserialize_trait_object_internally_tagged("type", &mut deserializer)?;
+      println!("Internally tagged deserialized: {:?}", roundtrip);
+    }
+
+    // The tag does not need to be the first field.
+    let json = r#"{"name":"B","type":"Foo"}"#;
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let deserialized = registry.deserialize_trait_object_internally_tagged("type", &mut deserializer)?;
+    println!("Internally tagged deserialized: {:?} from {}", deserialized, json);
+  }
+
+  { // Adjacently tagged: `{"t":"Foo","c":{"name":"A"}}`
+    for example in &examples {
+      let trait_object = example.as_ref();
+      let serialize = SerializeTraitObjectAdjacentlyTagged { tag: "t", content: "c", id: example.id(), trait_object };
+      let json = serde_json::to_string(&serialize)?;
+      println!("Adjacently tagged   serialized: {}", json);
+
+      let mut deserializer = serde_json::Deserializer::from_str(&json);
+      let roundtrip = registry.deserialize_trait_object_adjacently_tagged("t", "c", &mut deserializer)?;
+      println!("Adjacently tagged deserialized: {:?}", roundtrip);
+    }
+
+    // The content may come before the tag, in which case it is buffered.
+    let json = r#"{"c":{"count":1},"t":"Bar"}"#;
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let deserialized = registry.deserialize_trait_object_adjacently_tagged("t", "c", &mut deserializer)?;
+    println!("Adjacently tagged deserialized: {:?} from {}", deserialized, json);
+  }
+
+  { // Tuple: `["Foo",{"name":"A"}]`
+    for example in &examples {
+      let serialize = SerializeTraitObjectTuple { id: example.id(), trait_object: example.as_ref() };
+      let json = serde_json::to_string(&serialize)?;
+      println!("Tuple   serialized: {}", json);
+
+      let mut deserializer = serde_json::Deserializer::from_str(&json);
+      let roundtrip = registry.deserialize_trait_object_tuple(&mut deserializer)?;
+      println!("Tuple deserialized: {:?}", roundtrip);
+
+      // Also works with formats that are not self-describing, such as bincode.
+      let bytes = bincode::options().serialize(&serialize)?;
+      println!("Tuple   serialized: {:?} (bincode)", bytes);
+
+      let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+      let roundtrip = registry.deserialize_trait_object_tuple(&mut deserializer)?;
+      println!("Tuple deserialized: {:?} (bincode)", roundtrip);
+    }
+  }
+
+  { // Enum variant: `Foo((name:"A"))` in RON, and the variant index instead of the ID in bincode.
+    /// Wrapper that serializes a trait object as an enum variant, as the `Serialize` impl needs the registry.
+    struct Variant<'a>(&'a MapRegistry<dyn ExampleObj>, &'a dyn ExampleObj);
+    impl Serialize for Variant<'_> {
+      fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_trait_object_variant(serializer, self.0, self.1.id(), self.1)
+      }
+    }
+
+    for example in &examples {
+      let serialize = Variant(&registry, example.as_ref());
+      let ron = ron::to_string(&serialize)?;
+      println!("Enum variant   serialized: {}", ron);
+
+      let mut deserializer = ron::Deserializer::from_str(&ron)?;
+      let roundtrip = registry.deserialize_trait_object_variant(&mut deserializer)?;
+      println!("Enum variant deserialized: {:?}", roundtrip);
+
+      let bytes = bincode::options().serialize(&serialize)?;
+      println!("Enum variant   serialized: {:?} (bincode)", bytes);
+
+      let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+      let roundtrip = registry.deserialize_trait_object_variant(&mut deserializer)?;
+      println!("Enum variant deserialized: {:?} (bincode)", roundtrip);
+    }
+  }
+
+  { // Compact: `{"Foo":{"name":"A"}}` in JSON, and a compact ID instead of the ID in bincode.
+    for example in &examples {
+      let serialize = SerializeTraitObjectCompact { id: example.id(), trait_object: example.as_ref() };
+      let json = serde_json::to_string(&serialize)?;
+      println!("Compact   serialized: {}", json);
+
+      let mut deserializer = serde_json::Deserializer::from_str(&json);
+      let roundtrip = registry.deserialize_trait_object_compact(&mut deserializer)?;
+      println!("Compact deserialized: {:?}", roundtrip);
+
+      let bytes = bincode::options().serialize(&serialize)?;
+      println!("Compact   serialized: {:?} (bincode)", bytes);
+
+      let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+      let roundtrip = registry.deserialize_trait_object_compact(&mut deserializer)?;
+      println!("Compact deserialized: {:?} (bincode)", roundtrip);
+    }
+  }
+
+  { // Untagged: `{"name":"A"}`
+    for example in &examples {
+      let json = serialize_trait_object_untagged(serde_json::value::Serializer, example.as_ref())?.to_string();
+      println!("Untagged   serialized: {}", json);
+
+      let mut deserializer = serde_json::Deserializer::from_str(&json);
+      let roundtrip = registry.deserialize_trait_object_untagged(&mut deserializer)?;
+      println!("Untagged deserialized: {:?}", roundtrip);
+    }
+
+    // Only try an ordered list of candidates.
+    let json = r#"{"count":1}"#;
+    let deserialize = DeserializeTraitObjectUntagged { registry: &registry, candidates: Some(&[Bar::ID]) };
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let deserialized = deserialize.deserialize(&mut deserializer)?;
+    println!("Untagged deserialized: {:?} from {}", deserialized, json);
+  }
+
+  Ok(())
+}
+
+// Conversions into boxed trait objects, required by `Registry::register_type`.
+
+impl From<Foo> for Box<dyn ExampleObj> {
+  fn from(value: Foo) -> Self { Box::new(value) }
+}
+impl From<Bar> for Box<dyn ExampleObj> {
+  fn from(value: Bar) -> Self { Box::new(value) }
+}
@@ -0,0 +1,90 @@
+use std::error::Error;
+use std::fmt::Debug;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use serde_flexitos::{MapRegistry, Registry, serialize_trait_object};
+use serde_flexitos::scoped::{with_registry, with_scoped_registry};
+
+// Example trait
+
+pub trait ExampleObj: erased_serde::Serialize + Debug {
+  fn id(&self) -> &'static str;
+}
+
+// Example trait implementations
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Foo(String);
+impl Foo {
+  const ID: &'static str = "Foo";
+}
+impl ExampleObj for Foo {
+  fn id(&self) -> &'static str { Self::ID }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Bar(usize);
+impl Bar {
+  const ID: &'static str = "Bar";
+}
+impl ExampleObj for Bar {
+  fn id(&self) -> &'static str { Self::ID }
+}
+
+// (De)serialize implementations
+
+impl Serialize for dyn ExampleObj {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object(serializer, self.id(), self)
+  }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn ExampleObj> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    // Look up the registry that is in scope, instead of a global registry.
+    with_scoped_registry(|registry: &MapRegistry<dyn ExampleObj>| registry.deserialize_trait_object(deserializer))
+  }
+}
+
+// Struct containing trait objects, with derived (de)serialize implementations.
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Container {
+  name: String,
+  examples: Vec<Box<dyn ExampleObj>>,
+}
+
+// Run serialization roundtrip
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let mut registry = MapRegistry::<dyn ExampleObj>::new("ExampleObj");
+  registry.register_type::<Foo>(Foo::ID);
+  registry.register_type::<Bar>(Bar::ID);
+
+  let container = Container {
+    name: "container".to_string(),
+    examples: vec![Box::new(Foo("A".to_string())), Box::new(Bar(0))],
+  };
+  let json = serde_json::to_string(&container)?;
+  println!("`Container`   serialized: {}", json);
+
+  // Deserialize with the local registry in scope.
+  let roundtrip: Container = with_registry(&registry, || serde_json::from_str(&json))?;
+  println!("`Container` deserialized: {:?}", roundtrip);
+
+  // Deserializing without a registry in scope is an error.
+  let error = serde_json::from_str::<Container>(&json).unwrap_err();
+  println!("`Container` without registry: {}", error);
+
+  Ok(())
+}
+
+// Conversions into boxed trait objects, required by `Registry::register_type`.
+
+impl From<Foo> for Box<dyn ExampleObj> {
+  fn from(value: Foo) -> Self { Box::new(value) }
+}
+impl From<Bar> for Box<dyn ExampleObj> {
+  fn from(value: Bar) -> Self { Box::new(value) }
+}
@@ -0,0 +1,83 @@
+use std::error::Error;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize, Serializer};
+
+use serde_flexitos::{MapRegistry, Registry, serialize_trait_object};
+use serde_flexitos::de::DeserializeSeqWith;
+use serde_flexitos::shared::{DeserializeContext, DeserializeShared, SerializeContext, SerializeShared};
+
+// Example trait
+
+pub trait ExampleObj: erased_serde::Serialize + Debug {
+  fn id(&self) -> &'static str;
+}
+
+// Example trait implementations
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Foo(String);
+impl Foo {
+  const ID: &'static str = "Foo";
+}
+impl ExampleObj for Foo {
+  fn id(&self) -> &'static str { Self::ID }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Bar(usize);
+impl Bar {
+  const ID: &'static str = "Bar";
+}
+impl ExampleObj for Bar {
+  fn id(&self) -> &'static str { Self::ID }
+}
+
+// Serialize implementation
+
+impl Serialize for dyn ExampleObj {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object(serializer, self.id(), self)
+  }
+}
+
+// Run serialization roundtrip
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let mut registry = MapRegistry::<dyn ExampleObj>::new("ExampleObj");
+  registry.register_type::<Foo>(Foo::ID);
+  registry.register_type::<Bar>(Bar::ID);
+
+  let foo: Arc<dyn ExampleObj> = Arc::new(Foo("A".to_string()));
+  let bar: Arc<dyn ExampleObj> = Arc::new(Bar(0));
+  let examples = [foo.clone(), bar, foo];
+
+  // Serialize with a single context, writing back-references for `Arc`s that were serialized before.
+  let context = SerializeContext::new();
+  let serialize: Vec<_> = examples.iter()
+    .map(|trait_object| SerializeShared { context: &context, trait_object })
+    .collect();
+  let json = serde_json::to_string(&serialize)?;
+  println!("`Vec<Arc<dyn ExampleObj>>`   serialized: {}", json);
+
+  // Deserialize with a single context, restoring back-references as clones of the same `Arc`. The seed carrying the
+  // context composes with other seeds, such as `DeserializeSeqWith` for sequences.
+  let context = DeserializeContext::new();
+  let seed = DeserializeSeqWith::new(DeserializeShared { registry: &registry, context: &context });
+  let roundtrip: Vec<Arc<dyn ExampleObj>> = seed.deserialize(&mut serde_json::Deserializer::from_str(&json))?;
+  println!("`Vec<Arc<dyn ExampleObj>>` deserialized: {:?}", roundtrip);
+  println!("First and last element are the same `Arc`: {}", Arc::ptr_eq(&roundtrip[0], &roundtrip[2]));
+
+  Ok(())
+}
+
+// Conversions into boxed trait objects, required by `Registry::register_type`.
+
+impl From<Foo> for Box<dyn ExampleObj> {
+  fn from(value: Foo) -> Self { Box::new(value) }
+}
+impl From<Bar> for Box<dyn ExampleObj> {
+  fn from(value: Bar) -> Self { Box::new(value) }
+}
@@ -0,0 +1,68 @@
+use std::any::Any;
+use std::error::Error;
+use std::fmt::Debug;
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use serde_flexitos::{MapRegistry, Registry, serialize_trait_object_by_type_id};
+
+// Example trait, without an `id` method. The `Any` supertrait provides the `TypeId` of the concrete type instead.
+
+pub trait ExampleObj: erased_serde::Serialize + Any + Debug {}
+
+// Example trait implementations
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Foo(String);
+impl ExampleObj for Foo {}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Bar(usize);
+impl ExampleObj for Bar {}
+
+// Registry, registering IDs only once, along with the `TypeId` of each type
+
+static EXAMPLE_OBJ_REGISTRY: LazyLock<MapRegistry<dyn ExampleObj>> = LazyLock::new(|| {
+  let mut registry = MapRegistry::<dyn ExampleObj>::new("ExampleObj");
+  registry.register_type::<Foo>("Foo");
+  registry.register_type::<Bar>("Bar");
+  registry
+});
+
+// (De)serialize implementations
+
+impl Serialize for dyn ExampleObj {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    // `self.type_id()` dispatches to the concrete type, as `self` is a `dyn ExampleObj`.
+    serialize_trait_object_by_type_id(serializer, &*EXAMPLE_OBJ_REGISTRY, self.type_id(), self)
+  }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn ExampleObj> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    EXAMPLE_OBJ_REGISTRY.deserialize_trait_object(deserializer)
+  }
+}
+
+// Run serialization roundtrip
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let examples: Vec<Box<dyn ExampleObj>> = vec![Box::new(Foo("A".to_string())), Box::new(Bar(0))];
+  let json = serde_json::to_string(&examples)?;
+  println!("`Vec<Box<dyn ExampleObj>>`   serialized: {}", json);
+
+  let roundtrip: Vec<Box<dyn ExampleObj>> = serde_json::from_str(&json)?;
+  println!("`Vec<Box<dyn ExampleObj>>` deserialized: {:?}", roundtrip);
+
+  Ok(())
+}
+
+// Conversions into boxed trait objects, required by `Registry::register_type`.
+
+impl From<Foo> for Box<dyn ExampleObj> {
+  fn from(value: Foo) -> Self { Box::new(value) }
+}
+impl From<Bar> for Box<dyn ExampleObj> {
+  fn from(value: Bar) -> Self { Box::new(value) }
+}
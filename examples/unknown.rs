@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Debug;
+
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize};
+
+use serde_flexitos::{MapRegistry, Registry, serialize_trait_object};
+use serde_flexitos::permissive::{
+  PermissiveDeserializeMapWith, PermissiveDeserializeVecWithTraitObject, PreservingDeserializeTraitObject, Reason,
+  Skipped, Unknown,
+};
+
+// Example trait
+
+pub trait ExampleObj: erased_serde::Serialize + Debug {
+  fn id(&self) -> &str;
+}
+
+// Example trait implementations
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Foo {
+  name: String,
+}
+impl Foo {
+  const ID: &'static str = "Foo";
+}
+impl ExampleObj for Foo {
+  fn id(&self) -> &str { Self::ID }
+}
+
+// Unknown trait objects keep their ID, so that they serialize back into the same id-value pair.
+impl ExampleObj for Unknown {
+  fn id(&self) -> &str { Unknown::<String>::id(self) }
+}
+
+// (De)serialize implementations
+
+impl Serialize for dyn ExampleObj {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object(serializer, self.id(), self)
+  }
+}
+
+// Run serialization roundtrips
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let mut registry = MapRegistry::<dyn ExampleObj>::new("ExampleObj");
+  registry.register_type::<Foo>(Foo::ID);
+
+  // `Bar` is not registered, for example because it was serialized by a newer version of this program.
+  for json in [r#"{"Foo":{"name":"A"}}"#, r#"{"Bar":{"count":1,"tags":["x","y"]}}"#] {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let deserialized = PreservingDeserializeTraitObject::new(&registry).deserialize(&mut deserializer)?;
+    println!("`Box<dyn ExampleObj>` deserialized: {:?} from {}", deserialized, json);
+
+    let roundtrip = serde_json::to_string(&deserialized)?;
+    println!("`Box<dyn ExampleObj>`   serialized: {}", roundtrip);
+    assert_eq!(json, roundtrip);
+  }
+
+  { // Skip elements of unregistered types in a `Vec`, consuming their values.
+    let json = r#"[{"Foo":{"name":"A"}},{"Bar":{"count":1}},{"Foo":{"name":"B"}}]"#;
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    // Report skipped and failed trait objects, for example to log them.
+    let report = |trait_object_name: &'static str, id: &&str, reason: Reason<&str>| {
+      println!("Skipped or failed `Box<dyn {}>` with id '{}': {}", trait_object_name, id, reason);
+    };
+    let deserialized = PermissiveDeserializeVecWithTraitObject::with_diagnostics(&registry, &report)
+      .deserialize(&mut deserializer)?;
+    println!(
+      "`Vec<Box<dyn ExampleObj>>` deserialized: {:?}, skipping {}, from {}",
+      deserialized.value, deserialized.skipped, json
+    );
+  }
+
+  { // Skip entries with values of unregistered types in a `HashMap`.
+    let json = r#"{"a":{"Foo":{"name":"A"}},"b":{"Bar":{"count":1}}}"#;
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let deserialized: Skipped<HashMap<String, Box<dyn ExampleObj>>> =
+      PermissiveDeserializeMapWith::trait_object_value(&registry).deserialize(&mut deserializer)?;
+    println!(
+      "`HashMap<String, Box<dyn ExampleObj>>` deserialized: {:?}, skipping {}, from {}",
+      deserialized.value, deserialized.skipped, json
+    );
+  }
+
+  Ok(())
+}
+
+// Conversions into boxed trait objects, required by `Registry::register_type` and `PreservingDeserializeTraitObject`.
+
+impl From<Foo> for Box<dyn ExampleObj> {
+  fn from(value: Foo) -> Self { Box::new(value) }
+}
+impl From<Unknown> for Box<dyn ExampleObj> {
+  fn from(value: Unknown) -> Self { Box::new(value) }
+}
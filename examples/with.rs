@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Debug;
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+
+use serde_flexitos::{MapRegistry, Registry, serde_with_registry};
+
+// Example trait
+
+pub trait ExampleObj: erased_serde::Serialize + Debug {
+  fn id(&self) -> &'static str;
+}
+
+// Example trait implementations
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Foo(String);
+impl Foo {
+  const ID: &'static str = "Foo";
+}
+impl ExampleObj for Foo {
+  fn id(&self) -> &'static str { Self::ID }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+struct Bar(usize);
+impl Bar {
+  const ID: &'static str = "Bar";
+}
+impl ExampleObj for Bar {
+  fn id(&self) -> &'static str { Self::ID }
+}
+
+// Two registries for the same trait: one accepting all types, and one only accepting `Foo`. `Deserialize` cannot be
+// implemented for `Box<dyn ExampleObj>` for both of them, so generate `#[serde(with = "...")]` modules instead.
+
+static ALL_REGISTRY: LazyLock<MapRegistry<dyn ExampleObj>> = LazyLock::new(|| {
+  let mut registry = MapRegistry::<dyn ExampleObj>::new("ExampleObj");
+  registry.register_type::<Foo>(Foo::ID);
+  registry.register_type::<Bar>(Bar::ID);
+  registry
+});
+static FOO_REGISTRY: LazyLock<MapRegistry<dyn ExampleObj>> = LazyLock::new(|| {
+  let mut registry = MapRegistry::<dyn ExampleObj>::new("ExampleObj");
+  registry.register_type::<Foo>(Foo::ID);
+  registry
+});
+
+serde_with_registry!(mod all for dyn ExampleObj, registry: &*ALL_REGISTRY, id: ExampleObj::id);
+serde_with_registry!(mod only_foo for dyn ExampleObj, registry: &*FOO_REGISTRY, id: ExampleObj::id);
+
+// Struct containing trait objects, with derived (de)serialize implementations using the generated modules.
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Container {
+  #[serde(with = "all")]
+  single: Box<dyn ExampleObj>,
+  #[serde(with = "all::vec")]
+  multiple: Vec<Box<dyn ExampleObj>>,
+  #[serde(with = "only_foo::option", default)]
+  optional_foo: Option<Box<dyn ExampleObj>>,
+  #[serde(with = "only_foo::map")]
+  foos: HashMap<String, Box<dyn ExampleObj>>,
+}
+
+// Run serialization roundtrip
+
+fn main() -> Result<(), Box<dyn Error>> {
+  let container = Container {
+    single: Box::new(Bar(0)),
+    multiple: vec![Box::new(Foo("A".to_string())), Box::new(Bar(1))],
+    optional_foo: Some(Box::new(Foo("B".to_string()))),
+    foos: HashMap::from([("c".to_string(), Box::new(Foo("C".to_string())) as Box<dyn ExampleObj>)]),
+  };
+  let json = serde_json::to_string(&container)?;
+  println!("`Container`   serialized: {}", json);
+
+  let roundtrip: Container = serde_json::from_str(&json)?;
+  println!("`Container` deserialized: {:?}", roundtrip);
+
+  // `Bar` is not registered in the registry used for `optional_foo`.
+  let json = r#"{"single":{"Bar":0},"multiple":[],"optional_foo":{"Bar":1},"foos":{}}"#;
+  let error = serde_json::from_str::<Container>(json).unwrap_err();
+  println!("`Container` with `Bar` in `optional_foo`: {}", error);
+
+  Ok(())
+}
+
+// Conversions into boxed trait objects, required by `Registry::register_type`.
+
+impl From<Foo> for Box<dyn ExampleObj> {
+  fn from(value: Foo) -> Self { Box::new(value) }
+}
+impl From<Bar> for Box<dyn ExampleObj> {
+  fn from(value: Bar) -> Self { Box::new(value) }
+}
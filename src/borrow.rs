@@ -0,0 +1,246 @@
+//! Registry flavor for borrowed (zero-copy) deserialization, where deserialize functions produce trait objects that
+//! borrow from the input, such as trait objects of `struct Name<'a>(&'a str)`.
+//!
+//! As the trait object type depends on the lifetime `'de` of the input, registries are parameterized by a
+//! [`TraitObjectFamily`] that maps `'de` to the trait object type, such as `dyn Trait + 'de`. Use
+//! [`DeserializeBorrowTraitObject`] or [`deserialize_trait_object`](BorrowRegistry::deserialize_trait_object) to
+//! deserialize borrowed trait objects.
+//!
+//! ```
+//! # use std::fmt::Debug;
+//! # use serde::Deserialize;
+//! use serde_flexitos::borrow::{BorrowMapRegistry, BorrowRegistry, TraitObjectFamily};
+//!
+//! trait Example: Debug {}
+//!
+//! #[derive(Deserialize, Debug)]
+//! struct Name<'a>(&'a str);
+//! impl Example for Name<'_> {}
+//!
+//! struct ExampleFamily;
+//! impl TraitObjectFamily for ExampleFamily {
+//!   type TraitObject<'de> = dyn Example + 'de;
+//! }
+//!
+//! let mut registry = BorrowMapRegistry::<ExampleFamily>::new("Example");
+//! registry.register("Name", |d| Ok(Box::new(erased_serde::deserialize::<Name>(d)?)));
+//!
+//! let json = r#"{"Name":"A"}"#;
+//! let example = registry.deserialize_trait_object(&mut serde_json::Deserializer::from_str(json))?;
+//! assert_eq!(format!("{:?}", example), r#"Name("A")"#);
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Display, Formatter};
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+
+use crate::{GetError, RegistrationInfo};
+
+/// Family of trait object types that borrow from input with lifetime `'de`, such as `dyn Trait + 'de`.
+pub trait TraitObjectFamily: 'static {
+  /// The trait object type borrowing from input with lifetime `'de`.
+  type TraitObject<'de>: ?Sized + 'de;
+}
+
+/// Type alias for deserialize functions of trait objects of family `F` that borrow from the input.
+pub type BorrowDeserializeFn<F> = for<'de> fn(
+  &mut dyn erased_serde::Deserializer<'de>,
+) -> Result<Box<<F as TraitObjectFamily>::TraitObject<'de>>, erased_serde::Error>;
+
+/// Type alias for dynamic deserialize functions of trait objects of family `F` that borrow from the input, such as
+/// closures capturing state. Every [`BorrowDeserializeFn`] is also a dynamic deserialize function.
+pub type DynBorrowDeserializeFn<F> = dyn for<'de> Fn(
+  &mut dyn erased_serde::Deserializer<'de>,
+) -> Result<Box<<F as TraitObjectFamily>::TraitObject<'de>>, erased_serde::Error> + Send + Sync;
+
+/// Registry mapping unique identifiers of types to their borrowing deserialize implementations, enabling
+/// deserialization of trait objects that borrow from the input.
+pub trait BorrowRegistry {
+  /// The type of unique identifiers this registry uses.
+  type Identifier;
+  /// The family of trait object types this registry maps deserialize functions for.
+  type Family: TraitObjectFamily;
+
+  /// Register `deserialize_fn` as the deserialize function for `id`.
+  ///
+  /// This method is infallible, but errors such as multiple registrations for `id` may be propagated to
+  /// deserialization-time by making [get_deserialize_fn](Self::get_deserialize_fn) return an error.
+  #[inline]
+  #[track_caller]
+  fn register(&mut self, id: Self::Identifier, deserialize_fn: BorrowDeserializeFn<Self::Family>) {
+    self.register_boxed(id, Box::new(deserialize_fn));
+  }
+
+  /// Register dynamic `deserialize_fn` as the deserialize function for `id`.
+  ///
+  /// This method is infallible, but errors such as multiple registrations for `id` may be propagated to
+  /// deserialization-time by making [get_deserialize_fn](Self::get_deserialize_fn) return an error.
+  ///
+  /// Implementations may record a [`RegistrationInfo`] with the location of the caller, as this method tracks its
+  /// caller.
+  #[track_caller]
+  fn register_boxed(&mut self, id: Self::Identifier, deserialize_fn: Box<DynBorrowDeserializeFn<Self::Family>>);
+
+  /// Deserialize a trait object that borrows from the input with `deserializer`, using this registry to get the
+  /// deserialize function for the concrete type, based on the deserialized ID.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when [get_deserialize_fn](Self::get_deserialize_fn) returns an error for the deserialized ID, or
+  /// when deserialization fails.
+  #[inline]
+  fn deserialize_trait_object<'de, D>(
+    &self,
+    deserializer: D,
+  ) -> Result<Box<<Self::Family as TraitObjectFamily>::TraitObject<'de>>, D::Error> where
+    D: Deserializer<'de>,
+    Self: Sized,
+    Self::Identifier: Deserialize<'de> + Debug,
+  {
+    DeserializeBorrowTraitObject(self).deserialize(deserializer)
+  }
+
+  /// Gets the deserialize function for `id`.
+  ///
+  /// # Errors
+  ///
+  /// Implementations may return the following errors:
+  ///
+  /// - `GetError::NotRegistered { id }` if no deserialize function was registered for `id`.
+  /// - `GetError::MultipleRegistrations { id, registrations }` if multiple deserialize functions were registered for
+  ///   `id`. `registrations` has the recorded [`RegistrationInfo`] of each of them, if the implementation records it.
+  fn get_deserialize_fn(
+    &self,
+    id: Self::Identifier,
+  ) -> Result<&DynBorrowDeserializeFn<Self::Family>, GetError<Self::Identifier>>;
+
+  /// Gets the IDs that deserialize functions were registered for, including IDs with multiple registrations, in a
+  /// stable order.
+  fn get_ids(&self) -> Box<dyn Iterator<Item=&Self::Identifier> + '_>;
+
+  /// Gets the trait object name, for diagnostic purposes.
+  fn get_trait_object_name(&self) -> &'static str;
+}
+
+
+/// [BorrowRegistry] implementation mapping unique identifiers of type `I` to borrowing deserialize functions of trait
+/// object family `F`, using a [BTreeMap]. [get_ids](BorrowRegistry::get_ids) returns IDs in ascending order.
+pub struct BorrowMapRegistry<F: TraitObjectFamily, I = &'static str> {
+  /// Registrations by ID. Multiple registrations if multiple deserialize functions were registered for the same ID.
+  deserialize_fns: BTreeMap<I, Vec<Registration<F>>>,
+  trait_object_name: &'static str,
+}
+
+/// Registered deserialize function of a [BorrowMapRegistry], along with its diagnostic information.
+struct Registration<F: TraitObjectFamily> {
+  info: RegistrationInfo,
+  deserialize_fn: Box<DynBorrowDeserializeFn<F>>,
+}
+
+impl<F: TraitObjectFamily, I> BorrowMapRegistry<F, I> {
+  /// Creates a new registry, using `trait_object_name` as the name of the trait objects of `F` for diagnostic purposes.
+  #[inline]
+  pub fn new(trait_object_name: &'static str) -> Self {
+    Self {
+      deserialize_fns: BTreeMap::new(),
+      trait_object_name,
+    }
+  }
+}
+
+impl<F: TraitObjectFamily, I: Ord> BorrowRegistry for BorrowMapRegistry<F, I> {
+  type Identifier = I;
+  type Family = F;
+
+  #[inline]
+  fn register_boxed(&mut self, id: I, deserialize_fn: Box<DynBorrowDeserializeFn<F>>) {
+    let registration = Registration { info: RegistrationInfo::new(None), deserialize_fn };
+    self.deserialize_fns.entry(id).or_default().push(registration);
+  }
+
+  #[inline]
+  fn get_deserialize_fn(&self, id: I) -> Result<&DynBorrowDeserializeFn<F>, GetError<I>> {
+    let registrations = self.deserialize_fns.get(&id).map_or(&[][..], Vec::as_slice);
+    crate::get_single_registration(registrations, id, |registration| {
+      (registration.info, registration.deserialize_fn.as_ref())
+    })
+  }
+
+  #[inline]
+  fn get_ids(&self) -> Box<dyn Iterator<Item=&I> + '_> {
+    Box::new(self.deserialize_fns.keys())
+  }
+
+  #[inline]
+  fn get_trait_object_name(&self) -> &'static str {
+    self.trait_object_name
+  }
+}
+
+
+/// Deserialize [`Box<<R::Family as TraitObjectFamily>::TraitObject<'de>>`](Self::Value) from a single id-value pair,
+/// producing a trait object that borrows from the input, using the registry to get deserialize functions for concrete
+/// types of the trait object. Implements [`DeserializeSeed`].
+#[repr(transparent)]
+pub struct DeserializeBorrowTraitObject<'r, R>(pub &'r R);
+
+impl<'de, R: BorrowRegistry> DeserializeSeed<'de> for DeserializeBorrowTraitObject<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<<R::Family as TraitObjectFamily>::TraitObject<'de>>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_map(self)
+  }
+}
+
+impl<'de, R: BorrowRegistry> Visitor<'de> for DeserializeBorrowTraitObject<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<<R::Family as TraitObjectFamily>::TraitObject<'de>>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "an id-value pair for `Box<dyn {}>`", self.0.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+    let Some(id) = map.next_key::<R::Identifier>()? else {
+      return Err(de::Error::custom(self));
+    };
+    let deserialize_fn = self.0.get_deserialize_fn(id).map_err(de::Error::custom)?;
+    map.next_value_seed(DeserializeWithBorrowFn::<R::Family>(deserialize_fn))
+  }
+}
+
+impl<R> Copy for DeserializeBorrowTraitObject<'_, R> {}
+impl<R> Clone for DeserializeBorrowTraitObject<'_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+impl<'de, R: BorrowRegistry> Display for DeserializeBorrowTraitObject<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.expecting(f) }
+}
+
+
+/// Deserialize as a borrowed trait object of family `F` using given [deserialize function](DynBorrowDeserializeFn).
+#[repr(transparent)]
+struct DeserializeWithBorrowFn<'f, F: TraitObjectFamily>(&'f DynBorrowDeserializeFn<F>);
+
+impl<'de, F: TraitObjectFamily> DeserializeSeed<'de> for DeserializeWithBorrowFn<'_, F> {
+  type Value = Box<F::TraitObject<'de>>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+    self.0(&mut erased).map_err(de::Error::custom)
+  }
+}
@@ -0,0 +1,280 @@
+//! Fallible builder for registries, validating registrations at startup instead of at deserialization-time.
+//!
+//! [`RegistryBuilder`] implements [`Registry`], so types are registered with the usual methods, including from
+//! functions in [linkme](https://crates.io/crates/linkme) distributed slices. [`build`](RegistryBuilder::build) then
+//! returns a [`MapRegistry`], or a [`BuildError`] listing every invalid registration, along with the names of the
+//! registered types and the locations of the registrations:
+//! - IDs that multiple deserialize functions were registered for,
+//! - [`TypeId`]s that different IDs were registered for, which cannot be serialized by type ID,
+//! - with [`build_with_compact_ids`](RegistryBuilder::build_with_compact_ids), different IDs with the same
+//!   [compact ID](crate::compact_id), which cannot be told apart when deserializing compact IDs.
+//!
+//! ```
+//! # use serde::Deserialize;
+//! # use serde_flexitos::Registry;
+//! use serde_flexitos::builder::RegistryBuilder;
+//!
+//! trait Example {}
+//!
+//! #[derive(Deserialize)]
+//! struct Foo;
+//! impl Example for Foo {}
+//! impl From<Foo> for Box<dyn Example> {
+//!   fn from(value: Foo) -> Self { Box::new(value) }
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Bar;
+//! impl Example for Bar {}
+//! impl From<Bar> for Box<dyn Example> {
+//!   fn from(value: Bar) -> Self { Box::new(value) }
+//! }
+//!
+//! let mut builder = RegistryBuilder::<dyn Example>::new("Example");
+//! builder.register_type::<Foo>("Foo");
+//! builder.register_type::<Bar>("Foo");
+//! let Err(error) = builder.build() else { unreachable!() };
+//! assert_eq!(error.duplicates[0].id, "Foo");
+//! let registrations = &error.duplicates[0].registrations;
+//! assert!(registrations[0].type_name.unwrap().ends_with("Foo"));
+//! assert!(registrations[1].type_name.unwrap().ends_with("Bar"));
+//! ```
+
+use std::any::TypeId;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::{DynDeserializeFn, GetError, MapRegistry, Registry, RegistrationInfo};
+
+/// [Registry] implementation collecting registrations, which [build](Self::build) validates and turns into a
+/// [MapRegistry]. [get_ids](Registry::get_ids) returns IDs in ascending order.
+pub struct RegistryBuilder<O: ?Sized, I = &'static str> {
+  registrations: BTreeMap<I, Vec<Registration<O>>>,
+  type_ids: BTreeMap<TypeId, Vec<(I, RegistrationInfo)>>,
+  variants: &'static [&'static str],
+  trait_object_name: &'static str,
+}
+
+/// Registered deserialize function, along with its diagnostic information.
+struct Registration<O: ?Sized> {
+  info: RegistrationInfo,
+  deserialize_fn: Box<DynDeserializeFn<O>>,
+}
+
+impl<O: ?Sized, I> RegistryBuilder<O, I> {
+  /// Creates a new registry builder, using `trait_object_name` as the name of `O` for diagnostic purposes.
+  #[inline]
+  pub fn new(trait_object_name: &'static str) -> Self {
+    Self {
+      registrations: BTreeMap::new(),
+      type_ids: BTreeMap::new(),
+      variants: &[],
+      trait_object_name,
+    }
+  }
+
+  /// Sets the variant names of the [enum variant representation](crate::serialize_trait_object_variant) to `variants`,
+  /// which [build](Self::build) passes on to [MapRegistry::set_variants].
+  #[inline]
+  pub fn set_variants(&mut self, variants: &'static [&'static str]) {
+    self.variants = variants;
+  }
+}
+
+impl<O: ?Sized + 'static, I: Ord + Clone> RegistryBuilder<O, I> {
+  /// Builds a [MapRegistry] from the registrations of this builder.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [BuildError] listing every ID that multiple deserialize functions were registered for, and every
+  /// [`TypeId`] that different IDs were registered for.
+  #[inline]
+  pub fn build(self) -> Result<MapRegistry<O, I>, BuildError<I>> {
+    self.build_checked(Vec::new())
+  }
+
+  /// Builds a [MapRegistry] from the registrations of this builder, like [build](Self::build), but also checks that
+  /// different IDs have different [compact IDs](crate::compact_id), for registries that deserialize compact IDs.
+  ///
+  /// # Errors
+  ///
+  /// Returns a [BuildError] listing every ID that multiple deserialize functions were registered for, every set of
+  /// different IDs with the same compact ID, and every [`TypeId`] that different IDs were registered for.
+  pub fn build_with_compact_ids(self) -> Result<MapRegistry<O, I>, BuildError<I>> where
+    I: Display,
+  {
+    let mut ids_by_compact_id = BTreeMap::<u64, Vec<&I>>::new();
+    for id in self.registrations.keys() {
+      ids_by_compact_id.entry(crate::compact_id(id)).or_default().push(id);
+    }
+    let compact_id_collisions = ids_by_compact_id.into_iter()
+      .filter(|(_, ids)| ids.len() > 1)
+      .map(|(compact_id, ids)| CompactIdCollision { compact_id, ids: ids.into_iter().cloned().collect() })
+      .collect();
+    self.build_checked(compact_id_collisions)
+  }
+
+  fn build_checked(
+    self,
+    compact_id_collisions: Vec<CompactIdCollision<I>>,
+  ) -> Result<MapRegistry<O, I>, BuildError<I>> {
+    let duplicates: Vec<_> = self.registrations.iter()
+      .filter(|(_, registrations)| registrations.len() > 1)
+      .map(|(id, registrations)| {
+        let registrations = registrations.iter().map(|registration| registration.info).collect();
+        Duplicate { id: id.clone(), registrations }
+      })
+      .collect();
+    let type_id_conflicts: Vec<_> = self.type_ids.iter()
+      .filter(|(_, registrations)| registrations.iter().any(|(id, _)| *id != registrations[0].0))
+      .map(|(type_id, registrations)| TypeIdConflict { type_id: *type_id, registrations: registrations.clone() })
+      .collect();
+    if !duplicates.is_empty() || !compact_id_collisions.is_empty() || !type_id_conflicts.is_empty() {
+      return Err(BuildError {
+        trait_object_name: self.trait_object_name,
+        duplicates,
+        compact_id_collisions,
+        type_id_conflicts,
+      });
+    }
+
+    let mut registry = MapRegistry::new(self.trait_object_name);
+    registry.set_variants(self.variants);
+    for (type_id, registrations) in self.type_ids {
+      for (id, info) in registrations {
+        registry.register_type_id_with_info(type_id, id, info);
+      }
+    }
+    for (id, mut registrations) in self.registrations {
+      let Some(registration) = registrations.pop() else { continue; };
+      registry.register_boxed_with_info(id, registration.info, registration.deserialize_fn);
+    }
+    Ok(registry)
+  }
+}
+
+impl<O: ?Sized + 'static, I: Ord> Registry for RegistryBuilder<O, I> {
+  type Identifier = I;
+  type TraitObject = O;
+
+  #[inline]
+  fn register_boxed(&mut self, id: I, deserialize_fn: Box<DynDeserializeFn<O>>) {
+    self.register_boxed_with_info(id, RegistrationInfo::new(None), deserialize_fn);
+  }
+
+  #[inline]
+  fn register_boxed_with_info(&mut self, id: I, info: RegistrationInfo, deserialize_fn: Box<DynDeserializeFn<O>>) {
+    self.registrations.entry(id).or_default().push(Registration { info, deserialize_fn });
+  }
+
+  #[inline]
+  fn get_deserialize_fn(&self, id: I) -> Result<&DynDeserializeFn<O>, GetError<I>> {
+    let registrations = self.registrations.get(&id).map_or(&[][..], Vec::as_slice);
+    crate::get_single_registration(registrations, id, |registration| {
+      (registration.info, registration.deserialize_fn.as_ref())
+    })
+  }
+
+  #[inline]
+  fn register_type_id(&mut self, type_id: TypeId, id: I) {
+    self.register_type_id_with_info(type_id, id, RegistrationInfo::new(None));
+  }
+
+  #[inline]
+  fn register_type_id_with_info(&mut self, type_id: TypeId, id: I, info: RegistrationInfo) {
+    self.type_ids.entry(type_id).or_default().push((id, info));
+  }
+
+  #[inline]
+  fn get_id_by_type_id(&self, type_id: TypeId) -> Result<&I, GetError<TypeId>> {
+    crate::get_single_id(self.type_ids.get(&type_id).map_or(&[], Vec::as_slice), type_id)
+  }
+
+  #[inline]
+  fn get_ids(&self) -> Box<dyn Iterator<Item=&I> + '_> {
+    Box::new(self.registrations.keys())
+  }
+
+  #[inline]
+  fn get_variants(&self) -> &'static [&'static str] {
+    self.variants
+  }
+
+  #[inline]
+  fn get_trait_object_name(&self) -> &'static str {
+    self.trait_object_name
+  }
+}
+
+
+/// Error while building a registry: multiple deserialize functions were registered for one or more IDs, different IDs
+/// have the same compact ID, or different IDs were registered for the same type ID.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BuildError<I> {
+  /// Name of the trait object type of the registry.
+  pub trait_object_name: &'static str,
+  /// IDs that multiple deserialize functions were registered for, in ascending order.
+  pub duplicates: Vec<Duplicate<I>>,
+  /// Different IDs with the same compact ID, in ascending order of compact ID. Only checked by
+  /// [build_with_compact_ids](RegistryBuilder::build_with_compact_ids).
+  pub compact_id_collisions: Vec<CompactIdCollision<I>>,
+  /// Type IDs that different IDs were registered for, in ascending order of type ID.
+  pub type_id_conflicts: Vec<TypeIdConflict<I>>,
+}
+
+/// ID that multiple deserialize functions were registered for.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Duplicate<I> {
+  /// The ID.
+  pub id: I,
+  /// Diagnostic information of the registrations for the ID, such as the names of the registered types, in order of
+  /// registration.
+  pub registrations: Vec<RegistrationInfo>,
+}
+
+/// Different IDs with the same [compact ID](crate::compact_id).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CompactIdCollision<I> {
+  /// The compact ID.
+  pub compact_id: u64,
+  /// The IDs with the compact ID, in ascending order.
+  pub ids: Vec<I>,
+}
+
+/// Type ID that different IDs were registered for.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TypeIdConflict<I> {
+  /// The type ID.
+  pub type_id: TypeId,
+  /// IDs registered for the type ID along with the diagnostic information of their registrations, in order of
+  /// registration.
+  pub registrations: Vec<(I, RegistrationInfo)>,
+}
+
+impl<I: Debug> Error for BuildError<I> {}
+impl<I: Debug> Display for BuildError<I> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "invalid registrations for `dyn {}`:", self.trait_object_name)?;
+    for duplicate in &self.duplicates {
+      write!(f, " multiple deserialize functions were registered for id '{:?}':", duplicate.id)?;
+      for (i, registration) in duplicate.registrations.iter().enumerate() {
+        let separator = if i == 0 { " " } else { ", " };
+        write!(f, "{}{}", separator, registration)?;
+      }
+      f.write_str(";")?;
+    }
+    for collision in &self.compact_id_collisions {
+      write!(f, " ids {:?} have the same compact id '{:#x}';", collision.ids, collision.compact_id)?;
+    }
+    for conflict in &self.type_id_conflicts {
+      write!(f, " different ids were registered for type id '{:?}':", conflict.type_id)?;
+      for (i, (id, registration)) in conflict.registrations.iter().enumerate() {
+        let separator = if i == 0 { " " } else { ", " };
+        write!(f, "{}'{:?}' for {}", separator, id, registration)?;
+      }
+      f.write_str(";")?;
+    }
+    Ok(())
+  }
+}
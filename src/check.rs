@@ -0,0 +1,104 @@
+//! Consistency checks between the IDs that trait objects are serialized with, and the IDs their concrete types were
+//! registered with. A mismatch, such as an `id` method returning `"Foo"` while `Foo` is registered under `"Bar"`,
+//! produces data that deserializes into the wrong type or fails to deserialize.
+//!
+//! [`check_ids`] compares the serialize ID of a sample trait object per concrete type with the ID that was registered
+//! for the [`TypeId`] of that type, which [`register_type`](Registry::register_type) registers. Call it in a test or at
+//! startup:
+//!
+//! ```
+//! # use std::any::Any;
+//! # use serde::Deserialize;
+//! # use serde_flexitos::{MapRegistry, Registry};
+//! use serde_flexitos::check::check_ids;
+//!
+//! trait Example: Any {
+//!   fn id(&self) -> &'static str;
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Foo;
+//! impl Example for Foo {
+//!   fn id(&self) -> &'static str { "Foo" }
+//! }
+//! impl From<Foo> for Box<dyn Example> {
+//!   fn from(value: Foo) -> Self { Box::new(value) }
+//! }
+//!
+//! let mut registry = MapRegistry::<dyn Example>::new("Example");
+//! registry.register_type::<Foo>("Bar");
+//!
+//! let samples: [Box<dyn Example>; 1] = [Box::new(Foo)];
+//! let mismatches = check_ids(&registry, samples.iter().map(|s| s.as_ref()), |s| s.type_id(), |s| s.id()).unwrap_err();
+//! assert_eq!(mismatches[0].serialize_id, "Foo");
+//! assert_eq!(mismatches[0].registered_id, Ok("Bar"));
+//! ```
+
+use std::any::TypeId;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::{GetError, Registry};
+
+/// Checks that the serialize ID of each sample trait object in `samples`, obtained with `id`, is equal to the ID that
+/// was registered in `registry` for the [`TypeId`] of its concrete type, obtained with `type_id`.
+///
+/// For traits with an [`Any`](std::any::Any) supertrait, pass `|s| s.type_id()` as `type_id`, which dispatches to the
+/// concrete type of the trait object.
+///
+/// # Errors
+///
+/// Returns an [`IdMismatch`] for every sample whose serialize ID is not equal to the registered ID, including samples
+/// of types for which no ID or multiple IDs were registered.
+pub fn check_ids<'o, R, O>(
+  registry: &R,
+  samples: impl IntoIterator<Item=&'o O>,
+  type_id: impl Fn(&O) -> TypeId,
+  id: impl Fn(&O) -> R::Identifier,
+) -> Result<(), Vec<IdMismatch<R::Identifier>>> where
+  R: Registry + ?Sized,
+  R::Identifier: PartialEq + Clone,
+  O: ?Sized + 'o,
+{
+  let mismatches: Vec<_> = samples.into_iter().enumerate().filter_map(|(index, sample)| {
+    let type_id = type_id(sample);
+    let serialize_id = id(sample);
+    let registered_id = registry.get_id_by_type_id(type_id).cloned();
+    if registered_id.as_ref() == Ok(&serialize_id) {
+      None
+    } else {
+      Some(IdMismatch { index, type_id, serialize_id, registered_id })
+    }
+  }).collect();
+  if mismatches.is_empty() {
+    Ok(())
+  } else {
+    Err(mismatches)
+  }
+}
+
+
+/// Mismatch between the serialize ID of a sample trait object and the ID registered for its concrete type.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct IdMismatch<I> {
+  /// Index of the sample.
+  pub index: usize,
+  /// Type ID of the concrete type of the sample.
+  pub type_id: TypeId,
+  /// ID that the sample is serialized with.
+  pub serialize_id: I,
+  /// ID that was registered for the type ID, or the error from getting it.
+  pub registered_id: Result<I, GetError<TypeId>>,
+}
+
+impl<I: Debug> Error for IdMismatch<I> {}
+impl<I: Debug> Display for IdMismatch<I> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "sample {} is serialized with id '{:?}', but ", self.index, self.serialize_id)?;
+    match &self.registered_id {
+      Ok(registered_id) => write!(f, "its type is registered with id '{:?}'", registered_id),
+      Err(GetError::NotRegistered { .. }) => write!(f, "no id was registered for its type"),
+      Err(GetError::MultipleRegistrations { .. }) => write!(f, "multiple ids were registered for its type"),
+    }
+  }
+}
@@ -0,0 +1,472 @@
+//! Buffered [`Content`] of self-describing formats, for deserializing values whose concrete type is only known after
+//! (part of) the serialized data has been read.
+
+use std::fmt::{self, Formatter};
+use std::marker::PhantomData;
+
+use serde::de::{
+  self, Deserialize, Deserializer, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, Unexpected, VariantAccess,
+  Visitor,
+};
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+/// Buffered serialized data, capturing any value that a self-describing format can deserialize. Strings and bytes
+/// borrow from the serialized data (with lifetime `'de`) when the format supports it.
+///
+/// Deserialize [`Content`] from a [`Deserializer`], and deserialize from [`Content`] with a [`ContentDeserializer`].
+/// [`Content`] also implements [`Serialize`], reproducing the buffered data.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Content<'de> {
+  Bool(bool),
+
+  U8(u8),
+  U16(u16),
+  U32(u32),
+  U64(u64),
+  U128(u128),
+
+  I8(i8),
+  I16(i16),
+  I32(i32),
+  I64(i64),
+  I128(i128),
+
+  F32(f32),
+  F64(f64),
+
+  Char(char),
+  String(String),
+  Str(&'de str),
+  ByteBuf(Vec<u8>),
+  Bytes(&'de [u8]),
+
+  None,
+  Some(Box<Content<'de>>),
+
+  Unit,
+  Newtype(Box<Content<'de>>),
+  Seq(Vec<Content<'de>>),
+  Map(Vec<(Content<'de>, Content<'de>)>),
+}
+
+impl<'de> Content<'de> {
+  /// Returns the string if this is a string, or `None` otherwise.
+  #[inline]
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Content::String(s) => Some(s),
+      Content::Str(s) => Some(s),
+      _ => None,
+    }
+  }
+
+  /// Converts this content into content that does not borrow from the serialized data.
+  #[cfg(feature = "permissive")]
+  pub fn into_owned(self) -> Content<'static> {
+    match self {
+      Content::Bool(v) => Content::Bool(v),
+      Content::U8(v) => Content::U8(v),
+      Content::U16(v) => Content::U16(v),
+      Content::U32(v) => Content::U32(v),
+      Content::U64(v) => Content::U64(v),
+      Content::U128(v) => Content::U128(v),
+      Content::I8(v) => Content::I8(v),
+      Content::I16(v) => Content::I16(v),
+      Content::I32(v) => Content::I32(v),
+      Content::I64(v) => Content::I64(v),
+      Content::I128(v) => Content::I128(v),
+      Content::F32(v) => Content::F32(v),
+      Content::F64(v) => Content::F64(v),
+      Content::Char(v) => Content::Char(v),
+      Content::String(v) => Content::String(v),
+      Content::Str(v) => Content::String(v.to_owned()),
+      Content::ByteBuf(v) => Content::ByteBuf(v),
+      Content::Bytes(v) => Content::ByteBuf(v.to_owned()),
+      Content::None => Content::None,
+      Content::Some(v) => Content::Some(Box::new(v.into_owned())),
+      Content::Unit => Content::Unit,
+      Content::Newtype(v) => Content::Newtype(Box::new(v.into_owned())),
+      Content::Seq(v) => Content::Seq(v.into_iter().map(Content::into_owned).collect()),
+      Content::Map(v) => Content::Map(v.into_iter().map(|(k, v)| (k.into_owned(), v.into_owned())).collect()),
+    }
+  }
+
+  fn unexpected(&self) -> Unexpected<'_> {
+    match *self {
+      Content::Bool(v) => Unexpected::Bool(v),
+      Content::U8(v) => Unexpected::Unsigned(v as u64),
+      Content::U16(v) => Unexpected::Unsigned(v as u64),
+      Content::U32(v) => Unexpected::Unsigned(v as u64),
+      Content::U64(v) => Unexpected::Unsigned(v),
+      Content::U128(_) => Unexpected::Other("u128"),
+      Content::I8(v) => Unexpected::Signed(v as i64),
+      Content::I16(v) => Unexpected::Signed(v as i64),
+      Content::I32(v) => Unexpected::Signed(v as i64),
+      Content::I64(v) => Unexpected::Signed(v),
+      Content::I128(_) => Unexpected::Other("i128"),
+      Content::F32(v) => Unexpected::Float(v as f64),
+      Content::F64(v) => Unexpected::Float(v),
+      Content::Char(v) => Unexpected::Char(v),
+      Content::String(ref v) => Unexpected::Str(v),
+      Content::Str(v) => Unexpected::Str(v),
+      Content::ByteBuf(ref v) => Unexpected::Bytes(v),
+      Content::Bytes(v) => Unexpected::Bytes(v),
+      Content::None | Content::Some(_) => Unexpected::Option,
+      Content::Unit => Unexpected::Unit,
+      Content::Newtype(_) => Unexpected::NewtypeStruct,
+      Content::Seq(_) => Unexpected::Seq,
+      Content::Map(_) => Unexpected::Map,
+    }
+  }
+}
+
+
+// Deserialize implementation
+
+impl<'de> Deserialize<'de> for Content<'de> {
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    deserializer.deserialize_any(ContentVisitor)
+  }
+}
+
+/// Deserialize [`Content`] from any value.
+struct ContentVisitor;
+
+impl<'de> Visitor<'de> for ContentVisitor {
+  type Value = Content<'de>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    formatter.write_str("any value")
+  }
+
+  #[inline]
+  fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> { Ok(Content::Bool(v)) }
+
+  #[inline]
+  fn visit_i8<E: de::Error>(self, v: i8) -> Result<Self::Value, E> { Ok(Content::I8(v)) }
+  #[inline]
+  fn visit_i16<E: de::Error>(self, v: i16) -> Result<Self::Value, E> { Ok(Content::I16(v)) }
+  #[inline]
+  fn visit_i32<E: de::Error>(self, v: i32) -> Result<Self::Value, E> { Ok(Content::I32(v)) }
+  #[inline]
+  fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> { Ok(Content::I64(v)) }
+  #[inline]
+  fn visit_i128<E: de::Error>(self, v: i128) -> Result<Self::Value, E> { Ok(Content::I128(v)) }
+
+  #[inline]
+  fn visit_u8<E: de::Error>(self, v: u8) -> Result<Self::Value, E> { Ok(Content::U8(v)) }
+  #[inline]
+  fn visit_u16<E: de::Error>(self, v: u16) -> Result<Self::Value, E> { Ok(Content::U16(v)) }
+  #[inline]
+  fn visit_u32<E: de::Error>(self, v: u32) -> Result<Self::Value, E> { Ok(Content::U32(v)) }
+  #[inline]
+  fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> { Ok(Content::U64(v)) }
+  #[inline]
+  fn visit_u128<E: de::Error>(self, v: u128) -> Result<Self::Value, E> { Ok(Content::U128(v)) }
+
+  #[inline]
+  fn visit_f32<E: de::Error>(self, v: f32) -> Result<Self::Value, E> { Ok(Content::F32(v)) }
+  #[inline]
+  fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> { Ok(Content::F64(v)) }
+
+  #[inline]
+  fn visit_char<E: de::Error>(self, v: char) -> Result<Self::Value, E> { Ok(Content::Char(v)) }
+  #[inline]
+  fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> { Ok(Content::String(v.to_owned())) }
+  #[inline]
+  fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> { Ok(Content::Str(v)) }
+  #[inline]
+  fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> { Ok(Content::String(v)) }
+
+  #[inline]
+  fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> { Ok(Content::ByteBuf(v.to_owned())) }
+  #[inline]
+  fn visit_borrowed_bytes<E: de::Error>(self, v: &'de [u8]) -> Result<Self::Value, E> { Ok(Content::Bytes(v)) }
+  #[inline]
+  fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> { Ok(Content::ByteBuf(v)) }
+
+  #[inline]
+  fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> { Ok(Content::None) }
+  #[inline]
+  fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    Ok(Content::Some(Box::new(Content::deserialize(deserializer)?)))
+  }
+
+  #[inline]
+  fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> { Ok(Content::Unit) }
+  #[inline]
+  fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    Ok(Content::Newtype(Box::new(Content::deserialize(deserializer)?)))
+  }
+
+  #[inline]
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+    while let Some(element) = seq.next_element()? {
+      vec.push(element);
+    }
+    Ok(Content::Seq(vec))
+  }
+
+  #[inline]
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+    let mut vec = Vec::with_capacity(map.size_hint().unwrap_or(0).min(4096));
+    while let Some(entry) = map.next_entry()? {
+      vec.push(entry);
+    }
+    Ok(Content::Map(vec))
+  }
+
+  #[inline]
+  fn visit_enum<A: EnumAccess<'de>>(self, _data: A) -> Result<Self::Value, A::Error> {
+    Err(de::Error::custom("buffering enums is not supported; only self-describing data can be buffered"))
+  }
+}
+
+
+// Serialize implementation
+
+impl Serialize for Content<'_> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    match self {
+      Content::Bool(v) => serializer.serialize_bool(*v),
+      Content::U8(v) => serializer.serialize_u8(*v),
+      Content::U16(v) => serializer.serialize_u16(*v),
+      Content::U32(v) => serializer.serialize_u32(*v),
+      Content::U64(v) => serializer.serialize_u64(*v),
+      Content::U128(v) => serializer.serialize_u128(*v),
+      Content::I8(v) => serializer.serialize_i8(*v),
+      Content::I16(v) => serializer.serialize_i16(*v),
+      Content::I32(v) => serializer.serialize_i32(*v),
+      Content::I64(v) => serializer.serialize_i64(*v),
+      Content::I128(v) => serializer.serialize_i128(*v),
+      Content::F32(v) => serializer.serialize_f32(*v),
+      Content::F64(v) => serializer.serialize_f64(*v),
+      Content::Char(v) => serializer.serialize_char(*v),
+      Content::String(v) => serializer.serialize_str(v),
+      Content::Str(v) => serializer.serialize_str(v),
+      Content::ByteBuf(v) => serializer.serialize_bytes(v),
+      Content::Bytes(v) => serializer.serialize_bytes(v),
+      Content::None => serializer.serialize_none(),
+      Content::Some(v) => serializer.serialize_some(v.as_ref()),
+      Content::Unit => serializer.serialize_unit(),
+      Content::Newtype(v) => v.serialize(serializer),
+      Content::Seq(v) => {
+        let mut seq = serializer.serialize_seq(Some(v.len()))?;
+        for element in v {
+          seq.serialize_element(element)?;
+        }
+        seq.end()
+      }
+      Content::Map(v) => {
+        let mut map = serializer.serialize_map(Some(v.len()))?;
+        for (key, value) in v {
+          map.serialize_entry(key, value)?;
+        }
+        map.end()
+      }
+    }
+  }
+}
+
+
+// Deserializer implementation
+
+/// [`Deserializer`] that deserializes from buffered [`Content`], producing errors of type `E`.
+///
+/// [`is_human_readable`](Deserializer::is_human_readable) returns the value of the deserializer that the content was
+/// buffered from, so that types that (de)serialize differently in human-readable formats deserialize the same from
+/// buffered content.
+pub struct ContentDeserializer<'de, E> {
+  content: Content<'de>,
+  human_readable: bool,
+  _error: PhantomData<E>,
+}
+
+impl<'de, E> ContentDeserializer<'de, E> {
+  /// Creates a deserializer that deserializes from `content`, which was buffered from a deserializer whose
+  /// [`is_human_readable`](Deserializer::is_human_readable) returned `human_readable`.
+  #[inline]
+  pub fn new(content: Content<'de>, human_readable: bool) -> Self {
+    Self { content, human_readable, _error: PhantomData }
+  }
+}
+
+impl<'de, E: de::Error> IntoDeserializer<'de, E> for ContentDeserializer<'de, E> {
+  type Deserializer = Self;
+
+  #[inline]
+  fn into_deserializer(self) -> Self { self }
+}
+
+impl<'de, E: de::Error> Deserializer<'de> for ContentDeserializer<'de, E> {
+  type Error = E;
+
+  fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+    let human_readable = self.human_readable;
+    match self.content {
+      Content::Bool(v) => visitor.visit_bool(v),
+      Content::U8(v) => visitor.visit_u8(v),
+      Content::U16(v) => visitor.visit_u16(v),
+      Content::U32(v) => visitor.visit_u32(v),
+      Content::U64(v) => visitor.visit_u64(v),
+      Content::U128(v) => visitor.visit_u128(v),
+      Content::I8(v) => visitor.visit_i8(v),
+      Content::I16(v) => visitor.visit_i16(v),
+      Content::I32(v) => visitor.visit_i32(v),
+      Content::I64(v) => visitor.visit_i64(v),
+      Content::I128(v) => visitor.visit_i128(v),
+      Content::F32(v) => visitor.visit_f32(v),
+      Content::F64(v) => visitor.visit_f64(v),
+      Content::Char(v) => visitor.visit_char(v),
+      Content::String(v) => visitor.visit_string(v),
+      Content::Str(v) => visitor.visit_borrowed_str(v),
+      Content::ByteBuf(v) => visitor.visit_byte_buf(v),
+      Content::Bytes(v) => visitor.visit_borrowed_bytes(v),
+      Content::None => visitor.visit_none(),
+      Content::Some(v) => visitor.visit_some(ContentDeserializer::new(*v, human_readable)),
+      Content::Unit => visitor.visit_unit(),
+      Content::Newtype(v) => visitor.visit_newtype_struct(ContentDeserializer::new(*v, human_readable)),
+      Content::Seq(v) => {
+        let mut seq = SeqDeserializer::new(v.into_iter().map(|v| ContentDeserializer::new(v, human_readable)));
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(value)
+      }
+      Content::Map(v) => {
+        let mut map = MapDeserializer::new(v.into_iter().map(|(k, v)| {
+          (ContentDeserializer::new(k, human_readable), ContentDeserializer::new(v, human_readable))
+        }));
+        let value = visitor.visit_map(&mut map)?;
+        map.end()?;
+        Ok(value)
+      }
+    }
+  }
+
+  #[inline]
+  fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+    match self.content {
+      Content::None => visitor.visit_none(),
+      Content::Some(v) => visitor.visit_some(ContentDeserializer::new(*v, self.human_readable)),
+      Content::Unit => visitor.visit_unit(),
+      _ => visitor.visit_some(self),
+    }
+  }
+
+  #[inline]
+  fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+    match self.content {
+      // Allow unit types to be deserialized from an empty map, which remains of an internally tagged unit type.
+      Content::Map(ref v) if v.is_empty() => visitor.visit_unit(),
+      _ => self.deserialize_any(visitor),
+    }
+  }
+
+  #[inline]
+  fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, E> {
+    self.deserialize_unit(visitor)
+  }
+
+  #[inline]
+  fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, E> {
+    match self.content {
+      Content::Newtype(v) => visitor.visit_newtype_struct(ContentDeserializer::new(*v, self.human_readable)),
+      _ => visitor.visit_newtype_struct(self),
+    }
+  }
+
+  fn deserialize_enum<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    _variants: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, E> {
+    let (variant, value) = match self.content {
+      Content::Map(mut v) if v.len() == 1 => {
+        let (variant, value) = v.pop().unwrap();
+        (variant, Some(value))
+      }
+      content @ (Content::String(_) | Content::Str(_)) => (content, None),
+      content => return Err(de::Error::invalid_type(content.unexpected(), &"a map with a single entry or a string")),
+    };
+    visitor.visit_enum(EnumDeserializer { variant, value, human_readable: self.human_readable, _error: PhantomData })
+  }
+
+  #[inline]
+  fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, E> {
+    drop(self);
+    visitor.visit_unit()
+  }
+
+  #[inline]
+  fn is_human_readable(&self) -> bool { self.human_readable }
+
+  serde::forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf seq tuple tuple_struct map
+    struct identifier
+  }
+}
+
+/// [`EnumAccess`] and [`VariantAccess`] implementation for an enum variant buffered as [`Content`].
+struct EnumDeserializer<'de, E> {
+  variant: Content<'de>,
+  value: Option<Content<'de>>,
+  human_readable: bool,
+  _error: PhantomData<E>,
+}
+
+impl<'de, E: de::Error> EnumAccess<'de> for EnumDeserializer<'de, E> {
+  type Error = E;
+  type Variant = Self;
+
+  #[inline]
+  fn variant_seed<V: de::DeserializeSeed<'de>>(mut self, seed: V) -> Result<(V::Value, Self), E> {
+    let variant = std::mem::replace(&mut self.variant, Content::Unit);
+    let value = seed.deserialize(ContentDeserializer::new(variant, self.human_readable))?;
+    Ok((value, self))
+  }
+}
+
+impl<'de, E: de::Error> VariantAccess<'de> for EnumDeserializer<'de, E> {
+  type Error = E;
+
+  #[inline]
+  fn unit_variant(self) -> Result<(), E> {
+    match self.value {
+      None => Ok(()),
+      Some(value) => Deserialize::deserialize(ContentDeserializer::new(value, self.human_readable)),
+    }
+  }
+
+  #[inline]
+  fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, E> {
+    match self.value {
+      Some(value) => seed.deserialize(ContentDeserializer::new(value, self.human_readable)),
+      None => Err(de::Error::invalid_type(Unexpected::UnitVariant, &"newtype variant")),
+    }
+  }
+
+  #[inline]
+  fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, E> {
+    match self.value {
+      Some(value @ Content::Seq(_)) => ContentDeserializer::new(value, self.human_readable).deserialize_any(visitor),
+      Some(value) => Err(de::Error::invalid_type(value.unexpected(), &"tuple variant")),
+      None => Err(de::Error::invalid_type(Unexpected::UnitVariant, &"tuple variant")),
+    }
+  }
+
+  #[inline]
+  fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, E> {
+    match self.value {
+      Some(value @ (Content::Map(_) | Content::Seq(_))) => {
+        ContentDeserializer::new(value, self.human_readable).deserialize_any(visitor)
+      }
+      Some(value) => Err(de::Error::invalid_type(value.unexpected(), &"struct variant")),
+      None => Err(de::Error::invalid_type(Unexpected::UnitVariant, &"struct variant")),
+    }
+  }
+}
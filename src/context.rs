@@ -0,0 +1,340 @@
+//! Registry flavor for context-passing deserialization, where deserialize functions receive a caller-supplied context
+//! in addition to the deserializer. For example, a `&mut World` context can be used to resolve entity references while
+//! deserializing trait objects.
+//!
+//! Use [`DeserializeTraitObjectWith`] to thread the context through (nested) deserialization. To deserialize
+//! collections of trait objects, share the context in a [`RefCell`] with [`DeserializeTraitObjectWithCell`], or use
+//! [`DeserializeVecWithTraitObjectWith`] or [`DeserializeMapWith::trait_object_value_with`].
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+
+use crate::{GetError, RegistrationInfo};
+use crate::de::DeserializeMapWith;
+
+/// Type alias for deserialize functions of trait object type `O` that receive context `C`.
+pub type ContextDeserializeFn<O, C> =
+  for<'de> fn(&mut dyn erased_serde::Deserializer<'de>, &mut C) -> Result<Box<O>, erased_serde::Error>;
+
+/// Type alias for dynamic deserialize functions of trait object type `O` that receive context `C`, such as closures
+/// capturing state. Every [`ContextDeserializeFn`] is also a dynamic deserialize function.
+pub type DynContextDeserializeFn<O, C> = dyn for<'de> Fn(&mut dyn erased_serde::Deserializer<'de>, &mut C)
+  -> Result<Box<O>, erased_serde::Error> + Send + Sync;
+
+/// Registry mapping unique identifiers of types to their context-passing deserialize implementations, enabling
+/// deserialization of a specific trait object type with a context.
+pub trait ContextRegistry {
+  /// The type of unique identifiers this registry uses.
+  type Identifier;
+  /// The trait object type this registry maps deserialize functions for.
+  type TraitObject: ?Sized + 'static;
+  /// The context type that deserialize functions receive.
+  type Context: ?Sized;
+
+  /// Register `deserialize_fn` as the deserialize function for `id`.
+  ///
+  /// This method is infallible, but errors such as multiple registrations for `id` may be propagated to
+  /// deserialization-time by making [get_deserialize_fn](Self::get_deserialize_fn) return an error.
+  ///
+  /// Function pointers are only `'static` if their argument types are, so this method requires a `'static` context. Use
+  /// [register_boxed](Self::register_boxed) with a closure to register deserialize functions for contexts that borrow.
+  #[inline]
+  #[track_caller]
+  fn register(
+    &mut self,
+    id: Self::Identifier,
+    deserialize_fn: ContextDeserializeFn<Self::TraitObject, Self::Context>,
+  ) where
+    Self::Context: 'static,
+  {
+    self.register_boxed(id, Box::new(deserialize_fn));
+  }
+
+  /// Register dynamic `deserialize_fn` as the deserialize function for `id`.
+  ///
+  /// This method is infallible, but errors such as multiple registrations for `id` may be propagated to
+  /// deserialization-time by making [get_deserialize_fn](Self::get_deserialize_fn) return an error.
+  ///
+  /// Implementations may record a [`RegistrationInfo`] with the location of the caller, as this method tracks its
+  /// caller.
+  #[track_caller]
+  fn register_boxed(
+    &mut self,
+    id: Self::Identifier,
+    deserialize_fn: Box<DynContextDeserializeFn<Self::TraitObject, Self::Context>>,
+  );
+
+  /// Deserialize a trait object with `deserializer`, using this registry to get the deserialize function for the
+  /// concrete type, based on the deserialized ID, and passing `context` to the deserialize function.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when [get_deserialize_fn](Self::get_deserialize_fn) returns an error for the deserialized ID, or
+  /// when deserialization fails.
+  #[inline]
+  fn deserialize_trait_object_with<'de, D>(
+    &self,
+    context: &mut Self::Context,
+    deserializer: D,
+  ) -> Result<Box<Self::TraitObject>, D::Error> where
+    D: Deserializer<'de>,
+    Self: Sized,
+    Self::Identifier: Deserialize<'de> + Debug,
+  {
+    DeserializeTraitObjectWith { registry: self, context }.deserialize(deserializer)
+  }
+
+  /// Gets the deserialize function for `id`.
+  ///
+  /// # Errors
+  ///
+  /// Implementations may return the following errors:
+  ///
+  /// - `GetError::NotRegistered { id }` if no deserialize function was registered for `id`.
+  /// - `GetError::MultipleRegistrations { id, registrations }` if multiple deserialize functions were registered for
+  ///   `id`. `registrations` has the recorded [`RegistrationInfo`] of each of them, if the implementation records it.
+  #[allow(clippy::type_complexity)]
+  fn get_deserialize_fn(
+    &self,
+    id: Self::Identifier,
+  ) -> Result<&DynContextDeserializeFn<Self::TraitObject, Self::Context>, GetError<Self::Identifier>>;
+
+  /// Gets the IDs that deserialize functions were registered for, including IDs with multiple registrations, in a
+  /// stable order.
+  fn get_ids(&self) -> Box<dyn Iterator<Item=&Self::Identifier> + '_>;
+
+  /// Gets the trait object name, for diagnostic purposes.
+  fn get_trait_object_name(&self) -> &'static str;
+}
+
+
+/// [ContextRegistry] implementation mapping unique identifiers of type `I` to deserialize functions of trait object
+/// type `O` that receive context `C`, using a [BTreeMap]. [get_ids](ContextRegistry::get_ids) returns IDs in ascending
+/// order.
+pub struct ContextMapRegistry<O: ?Sized, C: ?Sized, I = &'static str> {
+  /// Registrations by ID. Multiple registrations if multiple deserialize functions were registered for the same ID.
+  deserialize_fns: BTreeMap<I, Vec<Registration<O, C>>>,
+  trait_object_name: &'static str,
+}
+
+/// Registered deserialize function of a [ContextMapRegistry], along with its diagnostic information.
+struct Registration<O: ?Sized, C: ?Sized> {
+  info: RegistrationInfo,
+  deserialize_fn: Box<DynContextDeserializeFn<O, C>>,
+}
+
+impl<O: ?Sized, C: ?Sized, I> ContextMapRegistry<O, C, I> {
+  /// Creates a new registry, using `trait_object_name` as the name of `O` for diagnostic purposes.
+  #[inline]
+  pub fn new(trait_object_name: &'static str) -> Self {
+    Self {
+      deserialize_fns: BTreeMap::new(),
+      trait_object_name,
+    }
+  }
+}
+
+impl<O: ?Sized + 'static, C: ?Sized, I: Ord> ContextRegistry for ContextMapRegistry<O, C, I> {
+  type Identifier = I;
+  type TraitObject = O;
+  type Context = C;
+
+  #[inline]
+  fn register_boxed(&mut self, id: I, deserialize_fn: Box<DynContextDeserializeFn<O, C>>) {
+    let registration = Registration { info: RegistrationInfo::new(None), deserialize_fn };
+    self.deserialize_fns.entry(id).or_default().push(registration);
+  }
+
+  #[inline]
+  fn get_deserialize_fn(&self, id: I) -> Result<&DynContextDeserializeFn<O, C>, GetError<I>> {
+    let registrations = self.deserialize_fns.get(&id).map_or(&[][..], Vec::as_slice);
+    crate::get_single_registration(registrations, id, |registration| {
+      (registration.info, registration.deserialize_fn.as_ref())
+    })
+  }
+
+  #[inline]
+  fn get_ids(&self) -> Box<dyn Iterator<Item=&I> + '_> {
+    Box::new(self.deserialize_fns.keys())
+  }
+
+  #[inline]
+  fn get_trait_object_name(&self) -> &'static str {
+    self.trait_object_name
+  }
+}
+
+
+/// Deserialize [`Box<<R as ContextRegistry>::TraitObject>`](Self::Value) from a single id-value pair, using the
+/// registry to get deserialize functions for concrete types of the trait object, and passing `context` to them.
+/// Implements [`DeserializeSeed`].
+pub struct DeserializeTraitObjectWith<'r, 'c, R: ContextRegistry> {
+  pub registry: &'r R,
+  pub context: &'c mut R::Context,
+}
+
+impl<'de, R: ContextRegistry> DeserializeSeed<'de> for DeserializeTraitObjectWith<'_, '_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_map(self)
+  }
+}
+
+impl<'de, R: ContextRegistry> Visitor<'de> for DeserializeTraitObjectWith<'_, '_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "an id-value pair for `Box<dyn {}>`", self.registry.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+    let Some(id) = map.next_key::<R::Identifier>()? else {
+      return Err(de::Error::custom(&self));
+    };
+    let deserialize_fn = self.registry.get_deserialize_fn(id).map_err(de::Error::custom)?;
+    map.next_value_seed(DeserializeWithContextFn(deserialize_fn, self.context))
+  }
+}
+
+impl<'de, R: ContextRegistry> Display for DeserializeTraitObjectWith<'_, '_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.expecting(f) }
+}
+
+
+/// Deserialize as `Box<O>` using given [deserialize function](DynContextDeserializeFn) and context.
+struct DeserializeWithContextFn<'f, 'c, O: ?Sized, C: ?Sized>(&'f DynContextDeserializeFn<O, C>, &'c mut C);
+
+impl<'de, O: ?Sized, C: ?Sized> DeserializeSeed<'de> for DeserializeWithContextFn<'_, '_, O, C> {
+  type Value = Box<O>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+    self.0(&mut erased, self.1).map_err(de::Error::custom)
+  }
+}
+
+
+/// Deserialize [`Box<<R as ContextRegistry>::TraitObject>`](Self::Value) from a single id-value pair, like
+/// [`DeserializeTraitObjectWith`], but with a shared `context` that is mutably borrowed while deserializing. Implements
+/// [`DeserializeSeed`].
+///
+/// Unlike [`DeserializeTraitObjectWith`], this seed implements [`Copy`], so it composes with seeds that deserialize
+/// multiple elements, such as [`DeserializeVecWithTraitObjectWith`] and
+/// [`DeserializeMapWith::trait_object_value_with`].
+///
+/// # Errors
+///
+/// Deserialization fails if `context` is already borrowed, for example when a deserialize function of the registry
+/// deserializes nested trait objects with this seed and the same `context`. Use [`DeserializeTraitObjectWith`] with the
+/// context passed to the deserialize function instead.
+pub struct DeserializeTraitObjectWithCell<'r, 'c, R: ContextRegistry> {
+  pub registry: &'r R,
+  pub context: &'c RefCell<R::Context>,
+}
+
+impl<'de, R: ContextRegistry> DeserializeSeed<'de> for DeserializeTraitObjectWithCell<'_, '_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    let mut context = self.context.try_borrow_mut().map_err(de::Error::custom)?;
+    DeserializeTraitObjectWith { registry: self.registry, context: &mut context }.deserialize(deserializer)
+  }
+}
+
+impl<R: ContextRegistry> Copy for DeserializeTraitObjectWithCell<'_, '_, R> {}
+impl<R: ContextRegistry> Clone for DeserializeTraitObjectWithCell<'_, '_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+
+/// Deserialize [`Vec<Box<<R as ContextRegistry>::TraitObject>>`](Self::Value), using the registry to get deserialize
+/// functions for concrete types of the trait object, and passing the shared `context` to them. Implements
+/// [`DeserializeSeed`].
+///
+/// # Errors
+///
+/// Deserialization fails if `context` is already borrowed, see [`DeserializeTraitObjectWithCell`].
+pub struct DeserializeVecWithTraitObjectWith<'r, 'c, R: ContextRegistry> {
+  pub registry: &'r R,
+  pub context: &'c RefCell<R::Context>,
+}
+
+impl<'de, R: ContextRegistry> DeserializeSeed<'de> for DeserializeVecWithTraitObjectWith<'_, '_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Vec<Box<R::TraitObject>>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_seq(self)
+  }
+}
+
+impl<'de, R: ContextRegistry> Visitor<'de> for DeserializeVecWithTraitObjectWith<'_, '_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Vec<Box<R::TraitObject>>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "a sequence of 'an id-value pair for `Box<dyn {}>`'", self.registry.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let mut vec = if let Some(capacity) = seq.size_hint() {
+      Vec::with_capacity(capacity)
+    } else {
+      Vec::new()
+    };
+    let seed = DeserializeTraitObjectWithCell { registry: self.registry, context: self.context };
+    while let Some(trait_object) = seq.next_element_seed(seed)? {
+      vec.push(trait_object);
+    }
+    Ok(vec)
+  }
+}
+
+impl<R: ContextRegistry> Copy for DeserializeVecWithTraitObjectWith<'_, '_, R> {}
+impl<R: ContextRegistry> Clone for DeserializeVecWithTraitObjectWith<'_, '_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+
+impl<'r, 'c, K, R, M> DeserializeMapWith<PhantomData<K>, DeserializeTraitObjectWithCell<'r, 'c, R>, M> where
+  R: ContextRegistry,
+{
+  /// Deserialize a map with `Box<R::TraitObject>` values, such as `HashMap<K, Box<R::TraitObject>>`, deserializing
+  /// `Box<R::TraitObject>` as a trait object using `registry` to get deserialize functions for concrete types of the
+  /// trait object, and passing the shared `context` to them.
+  ///
+  /// # Errors
+  ///
+  /// Deserialization fails if `context` is already borrowed, see [`DeserializeTraitObjectWithCell`].
+  #[inline]
+  pub fn trait_object_value_with(registry: &'r R, context: &'c RefCell<R::Context>) -> Self {
+    Self::new(PhantomData, DeserializeTraitObjectWithCell { registry, context })
+  }
+}
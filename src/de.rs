@@ -1,14 +1,19 @@
 //! [`DeserializeSeed`] and [`Visitor`] impls for deserializing trait objects and collections of trait objects.
 
-use std::collections::HashMap;
-use std::fmt::{self, Debug, Display, Formatter};
-use std::hash::Hash;
+use std::error::Error;
+use std::fmt::{self, Debug, Display, Formatter, Write};
 use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
 
-use serde::de::{self, Deserializer, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::de::{
+  self, Deserializer, DeserializeSeed, EnumAccess, IgnoredAny, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
 use serde::Deserialize;
 
-use crate::{DeserializeFn, Registry};
+use crate::content::{Content, ContentDeserializer};
+use crate::{DynDeserializeFn, GetError, Registry};
+use crate::key::{KeyCodec, KEY_SEPARATOR};
 
 /// Deserialize [`Box<<R as Registry>::TraitObject>`](Self::Value) from a single id-value pair, using the registry to
 /// get deserialize functions for concrete types of the trait object. Implements [`DeserializeSeed`].
@@ -41,7 +46,7 @@ impl<'de, R: Registry> Visitor<'de> for DeserializeTraitObject<'_, R> where
     // Visit a single id-value pair. Use `IdToDeserializeFn` to deserialize the ID as a string and then visit it,
     // turning it into `deserialize_fn`.
     let Some(deserialize_fn) = map.next_key_seed(IdToDeserializeFn(self.0))? else {
-      return Err(de::Error::custom(&self));
+      return Err(de::Error::custom(self));
     };
     // Use `DeserializeWithFn` to deserialize the value using `deserialize_fn`, resulting in a deserialized value
     // of trait object `O` (or an error).
@@ -62,28 +67,692 @@ impl<'de, R: Registry> Display for DeserializeTraitObject<'_, R> where
 }
 
 
+/// Deserialize [`Arc<<R as Registry>::TraitObject>`](Self::Value) from a single id-value pair, like
+/// [`DeserializeTraitObject`]. Implements [`DeserializeSeed`].
+#[repr(transparent)]
+pub struct DeserializeTraitObjectArc<'r, R>(pub &'r R);
+
+impl<'de, R: Registry> DeserializeSeed<'de> for DeserializeTraitObjectArc<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Arc<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    DeserializeTraitObject(self.0).deserialize(deserializer).map(Arc::from)
+  }
+}
+
+impl<R> Copy for DeserializeTraitObjectArc<'_, R> {}
+impl<R> Clone for DeserializeTraitObjectArc<'_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+
+/// Deserialize [`Rc<<R as Registry>::TraitObject>`](Self::Value) from a single id-value pair, like
+/// [`DeserializeTraitObject`]. Implements [`DeserializeSeed`].
+#[repr(transparent)]
+pub struct DeserializeTraitObjectRc<'r, R>(pub &'r R);
+
+impl<'de, R: Registry> DeserializeSeed<'de> for DeserializeTraitObjectRc<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Rc<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    DeserializeTraitObject(self.0).deserialize(deserializer).map(Rc::from)
+  }
+}
+
+impl<R> Copy for DeserializeTraitObjectRc<'_, R> {}
+impl<R> Clone for DeserializeTraitObjectRc<'_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+
+/// Deserialize [`Box<<R as Registry>::TraitObject>`](Self::Value) from a single id-value pair, using the registry to
+/// get deserialize functions for concrete types of the trait object. If the deserializer is not human-readable, the
+/// [compact ID](crate::compact_id) is deserialized instead of the ID. Implements [`DeserializeSeed`].
+#[repr(transparent)]
+pub struct DeserializeTraitObjectCompact<'r, R>(pub &'r R);
+
+impl<'de, R: Registry> DeserializeSeed<'de> for DeserializeTraitObjectCompact<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug + Display + Clone,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    if deserializer.is_human_readable() {
+      DeserializeTraitObject(self.0).deserialize(deserializer)
+    } else {
+      deserializer.deserialize_map(self)
+    }
+  }
+}
+
+impl<'de, R: Registry> Visitor<'de> for DeserializeTraitObjectCompact<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug + Display + Clone,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "a compact id-value pair for `Box<dyn {}>`", self.0.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+    let Some(compact_id) = map.next_key::<u64>()? else {
+      return Err(de::Error::custom(self));
+    };
+    let deserialize_fn = self.0.get_deserialize_fn_by_compact_id(compact_id).map_err(de::Error::custom)?;
+    map.next_value_seed(DeserializeWithFn(deserialize_fn))
+  }
+}
+
+impl<R> Copy for DeserializeTraitObjectCompact<'_, R> {}
+impl<R> Clone for DeserializeTraitObjectCompact<'_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+impl<'de, R: Registry> Display for DeserializeTraitObjectCompact<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug + Display + Clone,
+{
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.expecting(f) }
+}
+
+
+/// Deserialize [`Box<<R as Registry>::TraitObject>`](Self::Value) from an `(id, value)` tuple, using the registry to
+/// get deserialize functions for concrete types of the trait object. Implements [`DeserializeSeed`].
+#[repr(transparent)]
+pub struct DeserializeTraitObjectTuple<'r, R>(pub &'r R);
+
+impl<'de, R: Registry> DeserializeSeed<'de> for DeserializeTraitObjectTuple<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_tuple(2, self)
+  }
+}
+
+impl<'de, R: Registry> Visitor<'de> for DeserializeTraitObjectTuple<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "an (id, value) tuple for `Box<dyn {}>`", self.0.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let Some(deserialize_fn) = seq.next_element_seed(IdToDeserializeFn(self.0))? else {
+      return Err(de::Error::invalid_length(0, &self));
+    };
+    let Some(trait_object) = seq.next_element_seed(DeserializeWithFn(deserialize_fn))? else {
+      return Err(de::Error::invalid_length(1, &self));
+    };
+    Ok(trait_object)
+  }
+}
+
+impl<R> Copy for DeserializeTraitObjectTuple<'_, R> {}
+impl<R> Clone for DeserializeTraitObjectTuple<'_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+
+/// Deserialize [`Box<<R as Registry>::TraitObject>`](Self::Value) from a single string `id:value`, using the registry
+/// to get deserialize functions for concrete types of the trait object, and `codec` to decode the value with it. Use
+/// this for trait objects in map keys of formats that only support string keys, such as JSON. See the
+/// [key](crate::key) module for more info. Implements [`DeserializeSeed`].
+///
+/// The ID is matched against the [registered IDs](Registry::get_ids) by their [`Display`] form, as that is how
+/// [`serialize_trait_object_key`](crate::serialize_trait_object_key) serializes them. Therefore, this works for any
+/// identifier type, such as `&'static str` or `u32`.
+pub struct DeserializeTraitObjectKey<'r, R, C> {
+  pub registry: &'r R,
+  pub codec: C,
+}
+
+impl<'de, R: Registry, C: KeyCodec> DeserializeSeed<'de> for DeserializeTraitObjectKey<'_, R, C> where
+  R::Identifier: Display + Clone,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_str(self)
+  }
+}
+
+impl<'de, R: Registry, C: KeyCodec> Visitor<'de> for DeserializeTraitObjectKey<'_, R, C> where
+  R::Identifier: Display + Clone,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "an `id{}value` string for `Box<dyn {}>`", KEY_SEPARATOR, self.registry.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_str<E: de::Error>(self, key: &str) -> Result<Self::Value, E> {
+    let Some((id, value)) = key.split_once(KEY_SEPARATOR) else {
+      return Err(de::Error::invalid_value(de::Unexpected::Str(key), &self));
+    };
+    // The ID cannot be deserialized into `R::Identifier` in general: the string may not be borrowed, and identifiers
+    // such as `u32` do not deserialize from strings. Instead, match the registered IDs by their `Display` form.
+    let mut ids = self.registry.get_ids().filter(|registered| display_eq(*registered, id));
+    let deserialize_fn = match (ids.next(), ids.next()) {
+      (None, _) => Err(GetError::NotRegistered { id }),
+      (Some(_), Some(_)) => Err(GetError::MultipleRegistrations { id, registrations: Vec::new() }),
+      (Some(registered), None) => self.registry.get_deserialize_fn(registered.clone()).map_err(|e| e.with_id(id)),
+    }.map_err(de::Error::custom)?;
+    self.codec.decode(value, deserialize_fn).map_err(de::Error::custom)
+  }
+}
+
+impl<R, C: Copy> Copy for DeserializeTraitObjectKey<'_, R, C> {}
+impl<R, C: Clone> Clone for DeserializeTraitObjectKey<'_, R, C> {
+  #[inline]
+  fn clone(&self) -> Self { Self { registry: self.registry, codec: self.codec.clone() } }
+}
+
+
+/// Returns whether the [`Display`] form of `value` is equal to `string`, without allocating.
+#[inline]
+fn display_eq<T: Display + ?Sized>(value: &T, string: &str) -> bool {
+  /// Writer that strips written strings from the front of the remaining string, failing if they do not match.
+  struct StripPrefix<'s>(&'s str);
+  impl Write for StripPrefix<'_> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+      self.0 = self.0.strip_prefix(s).ok_or(fmt::Error)?;
+      Ok(())
+    }
+  }
+
+  let mut remaining = StripPrefix(string);
+  write!(remaining, "{}", value).is_ok() && remaining.0.is_empty()
+}
+
+
+/// Deserialize [`Box<<R as Registry>::TraitObject>`](Self::Value) from a newtype enum variant, where the variant name
+/// or index identifies the concrete type, using the registry to get deserialize functions for concrete types of the
+/// trait object. Implements [`DeserializeSeed`].
+///
+/// Variant names and indices are looked up in the [variants](Registry::get_variants) of the registry, which are also
+/// passed to [`Deserializer::deserialize_enum`].
+#[repr(transparent)]
+pub struct DeserializeTraitObjectVariant<'r, R>(pub &'r R);
+
+impl<'de, R: Registry<Identifier=&'static str>> DeserializeSeed<'de> for DeserializeTraitObjectVariant<'_, R> {
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_enum(self.0.get_trait_object_name(), self.0.get_variants(), self)
+  }
+}
+
+impl<'de, R: Registry<Identifier=&'static str>> Visitor<'de> for DeserializeTraitObjectVariant<'_, R> {
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "a newtype variant of `{}`", self.0.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+    let (deserialize_fn, variant) = data.variant_seed(VariantToDeserializeFn(self.0))?;
+    variant.newtype_variant_seed(DeserializeWithFn(deserialize_fn))
+  }
+}
+
+impl<R> Copy for DeserializeTraitObjectVariant<'_, R> {}
+impl<R> Clone for DeserializeTraitObjectVariant<'_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+
+/// Deserialize a variant name or index and use it to get its deserialize function from the registry.
+#[repr(transparent)]
+struct VariantToDeserializeFn<'r, R>(&'r R);
+
+impl<'r, R: Registry<Identifier=&'static str>> VariantToDeserializeFn<'r, R> {
+  #[inline]
+  fn get_deserialize_fn<E: de::Error>(&self, id: &'static str) -> Result<&'r DynDeserializeFn<R::TraitObject>, E> {
+    self.0.get_deserialize_fn(id).map_err(de::Error::custom)
+  }
+}
+
+impl<'de, 'r, R: Registry<Identifier=&'static str>> DeserializeSeed<'de> for VariantToDeserializeFn<'r, R> {
+  type Value = &'r DynDeserializeFn<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_identifier(self)
+  }
+}
+
+impl<'de, 'r, R: Registry<Identifier=&'static str>> Visitor<'de> for VariantToDeserializeFn<'r, R> {
+  type Value = &'r DynDeserializeFn<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "a variant name or index of `{}`", self.0.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+    let Some(id) = usize::try_from(v).ok().and_then(|index| self.0.get_variants().get(index)) else {
+      return Err(de::Error::invalid_value(de::Unexpected::Unsigned(v), &self));
+    };
+    self.get_deserialize_fn(id)
+  }
+
+  #[inline]
+  fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+    // Find the `&'static str` variant equal to `v`, as `v` does not live long enough.
+    let variants = self.0.get_variants();
+    let Some(id) = variants.iter().find(|id| **id == v) else {
+      return Err(de::Error::unknown_variant(v, variants));
+    };
+    self.get_deserialize_fn(id)
+  }
+
+  #[inline]
+  fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+    let variants = self.0.get_variants();
+    let Some(id) = variants.iter().find(|id| id.as_bytes() == v) else {
+      return Err(de::Error::unknown_variant(&String::from_utf8_lossy(v), variants));
+    };
+    self.get_deserialize_fn(id)
+  }
+}
+
+
+/// Deserialize [`Box<<R as Registry>::TraitObject>`](Self::Value) from a map with a `tag` field containing the ID, also
+/// known as the internally tagged representation, using the registry to get deserialize functions for concrete types of
+/// the trait object. Implements [`DeserializeSeed`].
+///
+/// The `tag` field may occur anywhere in the map. All other fields are buffered until the `tag` field is found, and are
+/// then deserialized with the deserialize function of the concrete type. Therefore, this only works with
+/// self-describing formats.
+pub struct DeserializeTraitObjectInternallyTagged<'r, R> {
+  pub registry: &'r R,
+  pub tag: &'static str,
+}
+
+impl<'de, R: Registry> DeserializeSeed<'de> for DeserializeTraitObjectInternallyTagged<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    let human_readable = deserializer.is_human_readable();
+    deserializer.deserialize_map(InternallyTaggedVisitor { registry: self.registry, tag: self.tag, human_readable })
+  }
+}
+
+impl<'de, R: Registry> Visitor<'de> for DeserializeTraitObjectInternallyTagged<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "a map with a `{}` field for `Box<dyn {}>`", self.tag, self.registry.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+    // Assume a human-readable format, as that is the default of `Deserializer::is_human_readable`.
+    InternallyTaggedVisitor { registry: self.registry, tag: self.tag, human_readable: true }.visit_map(map)
+  }
+}
+
+impl<R> Copy for DeserializeTraitObjectInternallyTagged<'_, R> {}
+impl<R> Clone for DeserializeTraitObjectInternallyTagged<'_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+/// Visitor for [`DeserializeTraitObjectInternallyTagged`], which also knows whether the deserializer is
+/// human-readable, so that the buffered fields are deserialized the same as they would be from the deserializer.
+struct InternallyTaggedVisitor<'r, R> {
+  registry: &'r R,
+  tag: &'static str,
+  human_readable: bool,
+}
+
+impl<'de, R: Registry> Visitor<'de> for InternallyTaggedVisitor<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "a map with a `{}` field for `Box<dyn {}>`", self.tag, self.registry.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+    // Buffer all fields except the tag, because the tag may come after other fields.
+    let mut id = None;
+    let mut fields = Vec::with_capacity(map.size_hint().unwrap_or(0).min(4096));
+    while let Some(key) = map.next_key::<Content>()? {
+      if key.as_str() == Some(self.tag) {
+        if id.is_some() {
+          return Err(de::Error::duplicate_field(self.tag));
+        }
+        id = Some(map.next_value::<R::Identifier>()?);
+      } else {
+        fields.push((key, map.next_value::<Content>()?));
+      }
+    }
+    let Some(id) = id else {
+      return Err(de::Error::missing_field(self.tag));
+    };
+    let deserialize_fn = self.registry.get_deserialize_fn(id).map_err(de::Error::custom)?;
+    // Deserialize the remaining fields with `deserialize_fn`.
+    let deserializer = ContentDeserializer::<A::Error>::new(Content::Map(fields), self.human_readable);
+    DeserializeWithFn(deserialize_fn).deserialize(deserializer)
+  }
+}
+
+
+/// Deserialize [`Box<<R as Registry>::TraitObject>`](Self::Value) from a map with a `tag` entry containing the ID and a
+/// `content` entry containing the value, also known as the adjacently tagged representation, using the registry to get
+/// deserialize functions for concrete types of the trait object. Implements [`DeserializeSeed`].
+///
+/// The entries may occur in either order. If the `content` entry occurs before the `tag` entry, it is buffered, which
+/// only works with self-describing formats. Other entries are ignored. As the `tag` entry is always serialized first,
+/// round-trips also work with formats that are not self-describing.
+pub struct DeserializeTraitObjectAdjacentlyTagged<'r, R> {
+  pub registry: &'r R,
+  pub tag: &'static str,
+  pub content: &'static str,
+}
+
+impl<'de, R: Registry> DeserializeSeed<'de> for DeserializeTraitObjectAdjacentlyTagged<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    let human_readable = deserializer.is_human_readable();
+    deserializer.deserialize_map(self.visitor(human_readable))
+  }
+}
+
+impl<'de, R: Registry> Visitor<'de> for DeserializeTraitObjectAdjacentlyTagged<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(
+      formatter,
+      "a map with `{}` and `{}` entries for `Box<dyn {}>`",
+      self.tag,
+      self.content,
+      self.registry.get_trait_object_name()
+    )
+  }
+
+  #[inline]
+  fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+    // Assume a human-readable format, as that is the default of `Deserializer::is_human_readable`.
+    self.visitor(true).visit_map(map)
+  }
+}
+
+impl<'r, R> DeserializeTraitObjectAdjacentlyTagged<'r, R> {
+  #[inline]
+  fn visitor(self, human_readable: bool) -> AdjacentlyTaggedVisitor<'r, R> {
+    AdjacentlyTaggedVisitor { registry: self.registry, tag: self.tag, content: self.content, human_readable }
+  }
+}
+
+impl<R> Copy for DeserializeTraitObjectAdjacentlyTagged<'_, R> {}
+impl<R> Clone for DeserializeTraitObjectAdjacentlyTagged<'_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+/// Visitor for [`DeserializeTraitObjectAdjacentlyTagged`], which also knows whether the deserializer is
+/// human-readable, so that a buffered `content` entry is deserialized the same as it would be from the deserializer.
+struct AdjacentlyTaggedVisitor<'r, R> {
+  registry: &'r R,
+  tag: &'static str,
+  content: &'static str,
+  human_readable: bool,
+}
+
+impl<'de, R: Registry> Visitor<'de> for AdjacentlyTaggedVisitor<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(
+      formatter,
+      "a map with `{}` and `{}` entries for `Box<dyn {}>`",
+      self.tag,
+      self.content,
+      self.registry.get_trait_object_name()
+    )
+  }
+
+  #[inline]
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+    let mut deserialize_fn = None;
+    let mut value = None;
+    let mut buffered = None;
+    let field_seed = AdjacentField { tag: self.tag, content: self.content };
+    while let Some(field) = map.next_key_seed(field_seed)? {
+      match field {
+        AdjacentFieldKind::Tag => {
+          if deserialize_fn.is_some() {
+            return Err(de::Error::duplicate_field(self.tag));
+          }
+          let id = map.next_value::<R::Identifier>()?;
+          deserialize_fn = Some(self.registry.get_deserialize_fn(id).map_err(de::Error::custom)?);
+        }
+        AdjacentFieldKind::Content => {
+          if value.is_some() || buffered.is_some() {
+            return Err(de::Error::duplicate_field(self.content));
+          }
+          // Deserialize the value directly if we know its deserialize function, otherwise buffer it.
+          if let Some(deserialize_fn) = deserialize_fn {
+            value = Some(map.next_value_seed(DeserializeWithFn(deserialize_fn))?);
+          } else {
+            buffered = Some(map.next_value::<Content>()?);
+          }
+        }
+        AdjacentFieldKind::Other => {
+          map.next_value::<IgnoredAny>()?;
+        }
+      }
+    }
+    let Some(deserialize_fn) = deserialize_fn else {
+      return Err(de::Error::missing_field(self.tag));
+    };
+    match (value, buffered) {
+      (Some(value), _) => Ok(value),
+      (None, Some(buffered)) => {
+        let deserializer = ContentDeserializer::<A::Error>::new(buffered, self.human_readable);
+        DeserializeWithFn(deserialize_fn).deserialize(deserializer)
+      }
+      (None, None) => Err(de::Error::missing_field(self.content)),
+    }
+  }
+}
+
+
+
+/// Deserialize a string as the kind of entry of an adjacently tagged trait object. Deserializes with
+/// [`Deserializer::deserialize_str`] instead of [`Deserializer::deserialize_identifier`], as formats that are not
+/// self-describing (such as bincode) do not support the latter.
+#[derive(Copy, Clone)]
+struct AdjacentField {
+  tag: &'static str,
+  content: &'static str,
+}
+
+enum AdjacentFieldKind {
+  Tag,
+  Content,
+  Other,
+}
+
+impl<'de> DeserializeSeed<'de> for AdjacentField {
+  type Value = AdjacentFieldKind;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_str(self)
+  }
+}
+
+impl<'de> Visitor<'de> for AdjacentField {
+  type Value = AdjacentFieldKind;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "`{}` or `{}`", self.tag, self.content)
+  }
+
+  #[inline]
+  fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+    if v == self.tag {
+      Ok(AdjacentFieldKind::Tag)
+    } else if v == self.content {
+      Ok(AdjacentFieldKind::Content)
+    } else {
+      Ok(AdjacentFieldKind::Other)
+    }
+  }
+
+  #[inline]
+  fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+    if v == self.tag.as_bytes() {
+      Ok(AdjacentFieldKind::Tag)
+    } else if v == self.content.as_bytes() {
+      Ok(AdjacentFieldKind::Content)
+    } else {
+      Ok(AdjacentFieldKind::Other)
+    }
+  }
+}
+
+
+/// Deserialize [`Box<<R as Registry>::TraitObject>`](Self::Value) from a value without an ID, also known as the
+/// untagged representation, by trying deserialize functions from the registry in order until one succeeds. Implements
+/// [`DeserializeSeed`].
+///
+/// If `candidates` is `Some`, only the deserialize functions of those IDs are tried, in the given order. Otherwise,
+/// the deserialize functions of all IDs from [`Registry::get_ids`] are tried, in that order. Registries that do not
+/// implement [`Registry::get_ids`] therefore require `candidates`.
+///
+/// The value is buffered in order to try multiple deserialize functions, which only works with self-describing formats.
+/// The first deserialize function that succeeds determines the concrete type, so order candidates from most to least
+/// specific. If getting the deserialize function of a candidate fails, for example because multiple deserialize
+/// functions were registered for it, that candidate is skipped. If no candidate succeeds, the error lists the ID and
+/// error of each candidate.
+pub struct DeserializeTraitObjectUntagged<'r, R: Registry> {
+  pub registry: &'r R,
+  pub candidates: Option<&'r [R::Identifier]>,
+}
+
+impl<'de, R: Registry> DeserializeSeed<'de> for DeserializeTraitObjectUntagged<'_, R> where
+  R::Identifier: Clone + Debug,
+{
+  type Value = Box<R::TraitObject>;
+
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    let human_readable = deserializer.is_human_readable();
+    let content = Content::deserialize(deserializer)?;
+    let ids: Box<dyn Iterator<Item=&R::Identifier>> = match self.candidates {
+      Some(candidates) => Box::new(candidates.iter()),
+      None => self.registry.get_ids(),
+    };
+    // Collect the error of each candidate, to report why the data did not match any of them.
+    let trait_object_name = self.registry.get_trait_object_name();
+    let mut message = format!("data did not match any concrete type of `Box<dyn {}>`", trait_object_name);
+    let mut tried = false;
+    for id in ids {
+      let separator = if tried { "; " } else { ": " };
+      tried = true;
+      let deserialize_fn = match self.registry.get_deserialize_fn(id.clone()) {
+        Ok(deserialize_fn) => deserialize_fn,
+        Err(e) => {
+          let _ = write!(message, "{}id '{:?}' was skipped: {}", separator, id, e);
+          continue;
+        }
+      };
+      let deserializer = ContentDeserializer::<D::Error>::new(content.clone(), human_readable);
+      match DeserializeWithFn(deserialize_fn).deserialize(deserializer) {
+        Ok(trait_object) => return Ok(trait_object),
+        Err(e) => {
+          let _ = write!(message, "{}id '{:?}' failed with: {}", separator, id, e);
+        }
+      }
+    }
+    if !tried {
+      message.push_str(": there are no candidates");
+    }
+    Err(de::Error::custom(message))
+  }
+}
+
+impl<R: Registry> Copy for DeserializeTraitObjectUntagged<'_, R> {}
+impl<R: Registry> Clone for DeserializeTraitObjectUntagged<'_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+
 /// Deserialize [`R::Identifier`](Registry::Identifier) and use it to get its deserialize function from the registry.
 #[repr(transparent)]
 struct IdToDeserializeFn<'r, R>(&'r R);
 
-impl<'de, R: Registry> DeserializeSeed<'de> for IdToDeserializeFn<'_, R> where
+impl<'de, 'r, R: Registry> DeserializeSeed<'de> for IdToDeserializeFn<'r, R> where
   R::Identifier: Deserialize<'de> + Debug,
 {
-  type Value = DeserializeFn<R::TraitObject>;
+  type Value = &'r DynDeserializeFn<R::TraitObject>;
 
   #[inline]
   fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
     let id = R::Identifier::deserialize(deserializer)?;
-    self.0.get_deserialize_fn(id).copied().map_err(|e| de::Error::custom(e))
+    self.0.get_deserialize_fn(id).map_err(de::Error::custom)
   }
 }
 
 
-/// Deserialize as `Box<O>` using given [deserialize function](DeserializeFn).
+/// Deserialize as `Box<O>` using given [deserialize function](DynDeserializeFn).
 #[repr(transparent)]
-pub(crate) struct DeserializeWithFn<O: ?Sized>(pub DeserializeFn<O>);
+pub(crate) struct DeserializeWithFn<'f, O: ?Sized>(pub &'f DynDeserializeFn<O>);
 
-impl<'de, O: ?Sized> DeserializeSeed<'de> for DeserializeWithFn<O> {
+impl<'de, O: ?Sized> DeserializeSeed<'de> for DeserializeWithFn<'_, O> {
   type Value = Box<O>;
 
   #[inline]
@@ -137,73 +806,226 @@ impl<'de, R: Registry> Visitor<'de> for DeserializeVecWithTraitObject<'_, R> whe
 }
 
 
-/// Deserialize `HashMap<K, V>`, using `key_deserialize_seed` to deserialize `K`, and `value_deserialize_seed` to
-/// deserialize `V`. Implements [`DeserializeSeed`]. Use the following functions to create instances of this struct:
+/// Deserialize [`Vec<Box<<R as Registry>::TraitObject>>`](Collected::value), using the registry to get deserialize
+/// functions for concrete types of the trait object. Instead of aborting on the first element that fails to
+/// deserialize, continues with the next element and collects an [`ElementError`] for each failing element. Implements
+/// [`DeserializeSeed`].
+///
+/// Only collecting errors into a [`Vec`] is supported. Each element is buffered before it is deserialized, which
+/// requires a self-describing format such as JSON; formats such as bincode fail to deserialize. Errors of the format
+/// itself, such as syntax errors, still abort deserialization.
+#[repr(transparent)]
+pub struct DeserializeVecWithTraitObjectCollectErrors<'r, R>(pub &'r R);
+
+impl<'de, R: Registry> DeserializeSeed<'de> for DeserializeVecWithTraitObjectCollectErrors<'_, R> where
+  R::Identifier: Deserialize<'de> + Clone + Debug,
+{
+  type Value = Collected<Vec<Box<R::TraitObject>>, R::Identifier>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    // Deserialize buffered elements the same as they would be deserialized from `deserializer`.
+    let human_readable = deserializer.is_human_readable();
+    deserializer.deserialize_seq(CollectErrorsVisitor { registry: self.0, human_readable })
+  }
+}
+
+impl<'de, R: Registry> Visitor<'de> for DeserializeVecWithTraitObjectCollectErrors<'_, R> where
+  R::Identifier: Deserialize<'de> + Clone + Debug,
+{
+  type Value = Collected<Vec<Box<R::TraitObject>>, R::Identifier>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    formatter.write_str("a sequence of '")?;
+    DeserializeTraitObject(self.0).expecting(formatter)?;
+    formatter.write_str("'")
+  }
+
+  #[inline]
+  fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+    // Assume a human-readable format, as that is the default of `Deserializer::is_human_readable`.
+    CollectErrorsVisitor { registry: self.0, human_readable: true }.visit_seq(seq)
+  }
+}
+
+impl<R> Copy for DeserializeVecWithTraitObjectCollectErrors<'_, R> {}
+impl<R> Clone for DeserializeVecWithTraitObjectCollectErrors<'_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+/// [`Visitor`] of [`DeserializeVecWithTraitObjectCollectErrors`], deserializing buffered elements as human-readable if
+/// the original deserializer is.
+struct CollectErrorsVisitor<'r, R> {
+  registry: &'r R,
+  human_readable: bool,
+}
+
+impl<'de, R: Registry> Visitor<'de> for CollectErrorsVisitor<'_, R> where
+  R::Identifier: Deserialize<'de> + Clone + Debug,
+{
+  type Value = Collected<Vec<Box<R::TraitObject>>, R::Identifier>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    DeserializeVecWithTraitObjectCollectErrors(self.registry).expecting(formatter)
+  }
+
+  #[inline]
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let mut vec = if let Some(capacity) = seq.size_hint() {
+      Vec::with_capacity(capacity)
+    } else {
+      Vec::new()
+    };
+    let mut errors = Vec::new();
+    let mut index = 0;
+    while let Some(content) = seq.next_element::<Content>()? {
+      match self.deserialize_element(index, content) {
+        Ok(trait_object) => vec.push(trait_object),
+        Err(e) => errors.push(e),
+      }
+      index += 1;
+    }
+    Ok(Collected { value: vec, errors })
+  }
+}
+
+impl<'de, R: Registry> CollectErrorsVisitor<'_, R> where
+  R::Identifier: Deserialize<'de> + Clone + Debug,
+{
+  /// Deserialize a single buffered element at `index`.
+  fn deserialize_element(
+    &self,
+    index: usize,
+    content: Content<'de>,
+  ) -> Result<Box<R::TraitObject>, ElementError<R::Identifier>> {
+    let error = |id, e: de::value::Error| ElementError { index, id, message: e.to_string() };
+    let deserializer = |content| ContentDeserializer::new(content, self.human_readable);
+    let (id, value) = match content {
+      Content::Map(mut entries) if entries.len() == 1 => entries.pop().unwrap(),
+      // Not an id-value pair: let `DeserializeTraitObject` produce the error.
+      content => {
+        return DeserializeTraitObject(self.registry).deserialize(deserializer(content)).map_err(|e| error(None, e));
+      }
+    };
+    let id = R::Identifier::deserialize(deserializer(id)).map_err(|e| error(None, e))?;
+    match self.registry.get_deserialize_fn(id.clone()) {
+      Ok(deserialize_fn) => DeserializeWithFn(deserialize_fn)
+        .deserialize(deserializer(value))
+        .map_err(|e| error(Some(id), e)),
+      Err(e) => Err(error(Some(id), de::Error::custom(e))),
+    }
+  }
+}
+
+/// Value of a collect-all-errors deserialization, along with the errors of elements that failed to deserialize.
+#[derive(Debug)]
+pub struct Collected<T, I> {
+  pub value: T,
+  pub errors: Vec<ElementError<I>>,
+}
+
+/// Error of a single element of a collection that failed to deserialize.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ElementError<I> {
+  /// Index of the element in the collection.
+  pub index: usize,
+  /// ID of the concrete type of the element, or `None` if the element is not an id-value pair or its ID failed to
+  /// deserialize.
+  pub id: Option<I>,
+  /// Error message.
+  pub message: String,
+}
+impl<I: Debug> Error for ElementError<I> {}
+impl<I: Debug> Display for ElementError<I> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match &self.id {
+      Some(id) => write!(f, "element {} with id '{:?}': {}", self.index, id, self.message),
+      None => write!(f, "element {}: {}", self.index, self.message),
+    }
+  }
+}
+
+
+/// Deserialize a map of type `M`, such as `HashMap<K::Value, V::Value>` or `BTreeMap<K::Value, V::Value>`, using
+/// `key_deserialize_seed` to deserialize keys, and `value_deserialize_seed` to deserialize values. `M` can be any map
+/// that implements [`Default`] and [`Extend`], and preallocates capacity from the size hint of the deserializer if its
+/// [`Extend`] implementation reserves capacity, as `HashMap` does. Implements [`DeserializeSeed`]. Use the following
+/// functions to create instances of this struct:
+/// - [new](Self::new): deserialize map keys and values with custom seeds,
 /// - [trait_object_key](Self::trait_object_key): deserialize map keys as trait objects,
 /// - [trait_object_value](Self::trait_object_value): deserialize map values as trait objects,
 /// - [trait_object_key_and_value](Self::trait_object_key_and_value): deserialize map keys and values as trait objects.
-pub struct DeserializeMapWith<K, V> {
+pub struct DeserializeMapWith<K, V, M> {
   key_deserialize_seed: K,
   value_deserialize_seed: V,
+  map: PhantomData<fn() -> M>,
+}
+
+impl<K, V, M> DeserializeMapWith<K, V, M> {
+  /// Deserialize `M`, using `key_deserialize_seed` to deserialize keys, and `value_deserialize_seed` to deserialize
+  /// values.
+  #[inline]
+  pub fn new(key_deserialize_seed: K, value_deserialize_seed: V) -> Self {
+    Self { key_deserialize_seed, value_deserialize_seed, map: PhantomData }
+  }
 }
 
-impl<'k, K, V, R> DeserializeMapWith<DeserializeTraitObject<'k, R>, PhantomData<V>> where
-  K: Eq + Hash + ?Sized,
+impl<'k, K, V, R, M> DeserializeMapWith<DeserializeTraitObject<'k, R>, PhantomData<V>, M> where
+  K: ?Sized,
   R: Registry<TraitObject=K>
 {
-  /// Deserialize `HashMap<Box<K>, V>`, deserializing `Box<K>` as a trait object where `K` is the trait object type,
-  /// using `registry` to get deserialize functions for concrete types of trait object `K`.
+  /// Deserialize a map with `Box<K>` keys, such as `HashMap<Box<K>, V>`, deserializing `Box<K>` as a trait object where
+  /// `K` is the trait object type, using `registry` to get deserialize functions for concrete types of trait object
+  /// `K`.
   #[inline]
   pub fn trait_object_key(registry: &'k R) -> Self {
-    Self {
-      key_deserialize_seed: DeserializeTraitObject(registry),
-      value_deserialize_seed: PhantomData::default(),
-    }
+    Self::new(DeserializeTraitObject(registry), PhantomData)
   }
 }
 
-impl<'v, K, V, R> DeserializeMapWith<PhantomData<K>, DeserializeTraitObject<'v, R>> where
-  K: Eq + Hash,
+impl<'v, K, V, R, M> DeserializeMapWith<PhantomData<K>, DeserializeTraitObject<'v, R>, M> where
   V: ?Sized,
   R: Registry<TraitObject=V>
 {
-  /// Deserialize `HashMap<K, Box<V>>`, deserializing `Box<V>` as a trait object where `V` is the trait object type,
-  /// using `registry` to get deserialize functions for concrete types of trait object `V`.
+  /// Deserialize a map with `Box<V>` values, such as `HashMap<K, Box<V>>` or `BTreeMap<K, Box<V>>`, deserializing
+  /// `Box<V>` as a trait object where `V` is the trait object type, using `registry` to get deserialize functions for
+  /// concrete types of trait object `V`.
   #[inline]
   pub fn trait_object_value(registry: &'v R) -> Self {
-    Self {
-      key_deserialize_seed: PhantomData::default(),
-      value_deserialize_seed: DeserializeTraitObject(registry),
-    }
+    Self::new(PhantomData, DeserializeTraitObject(registry))
   }
 }
 
-impl<'k, 'v, K, RK, V, RV> DeserializeMapWith<DeserializeTraitObject<'k, RK>, DeserializeTraitObject<'v, RV>> where
-  K: Eq + Hash + ?Sized,
+impl<'k, 'v, K, RK, V, RV, M> DeserializeMapWith<
+  DeserializeTraitObject<'k, RK>,
+  DeserializeTraitObject<'v, RV>,
+  M,
+> where
+  K: ?Sized,
   V: ?Sized,
   RK: Registry<TraitObject=K>,
   RV: Registry<TraitObject=V>
 {
-  /// Deserialize `HashMap<Box<K>, Box<V>>`:
+  /// Deserialize a map with `Box<K>` keys and `Box<V>` values, such as `HashMap<Box<K>, Box<V>>`:
   /// - deserialize `Box<K>` as a trait object where `K` is the trait object type, using `key_registry` to get
   ///   deserialize functions for concrete types of trait object `K`.
   /// - deserialize `Box<V>` as a trait object where `V` is the trait object type, using `value_registry` to get
   ///   deserialize functions for concrete types of trait object `V`.
   #[inline]
   pub fn trait_object_key_and_value(key_registry: &'k RK, value_registry: &'v RV) -> Self {
-    Self {
-      key_deserialize_seed: DeserializeTraitObject(key_registry),
-      value_deserialize_seed: DeserializeTraitObject(value_registry),
-    }
+    Self::new(DeserializeTraitObject(key_registry), DeserializeTraitObject(value_registry))
   }
 }
 
-impl<'de, K, V> DeserializeSeed<'de> for DeserializeMapWith<K, V> where
-  K: DeserializeSeed<'de> + Copy,
-  K::Value: Eq + Hash,
-  V: DeserializeSeed<'de> + Copy,
+impl<'de, K, V, M> DeserializeSeed<'de> for DeserializeMapWith<K, V, M> where
+  K: DeserializeSeed<'de> + Clone,
+  V: DeserializeSeed<'de> + Clone,
+  M: Default + Extend<(K::Value, V::Value)>,
 {
-  type Value = HashMap<K::Value, V::Value>;
+  type Value = M;
 
   #[inline]
   fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
@@ -211,12 +1033,12 @@ impl<'de, K, V> DeserializeSeed<'de> for DeserializeMapWith<K, V> where
   }
 }
 
-impl<'de, K, V> Visitor<'de> for DeserializeMapWith<K, V> where
-  K: DeserializeSeed<'de> + Copy,
-  K::Value: Eq + Hash,
-  V: DeserializeSeed<'de> + Copy,
+impl<'de, K, V, M> Visitor<'de> for DeserializeMapWith<K, V, M> where
+  K: DeserializeSeed<'de> + Clone,
+  V: DeserializeSeed<'de> + Clone,
+  M: Default + Extend<(K::Value, V::Value)>,
 {
-  type Value = HashMap<K::Value, V::Value>;
+  type Value = M;
 
   #[inline]
   fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
@@ -225,15 +1047,294 @@ impl<'de, K, V> Visitor<'de> for DeserializeMapWith<K, V> where
 
   #[inline]
   fn visit_map<A: MapAccess<'de>>(self, mut map_access: A) -> Result<Self::Value, A::Error> {
-    let mut map = if let Some(capacity) = map_access.size_hint() {
-      HashMap::with_capacity(capacity)
-    } else {
-      HashMap::new()
-    };
-    while let Some(key) = map_access.next_key_seed(self.key_deserialize_seed)? {
-      let value = map_access.next_value_seed(self.value_deserialize_seed)?;
-      map.insert(key, value);
-    }
+    let mut map = M::default();
+    try_extend(&mut map, map_access.size_hint(), || {
+      let Some(key) = map_access.next_key_seed(self.key_deserialize_seed.clone())? else { return Ok(None) };
+      let value = map_access.next_value_seed(self.value_deserialize_seed.clone())?;
+      Ok(Some((key, value)))
+    })?;
     Ok(map)
   }
 }
+
+impl<K: Copy, V: Copy, M> Copy for DeserializeMapWith<K, V, M> {}
+impl<K: Clone, V: Clone, M> Clone for DeserializeMapWith<K, V, M> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self::new(self.key_deserialize_seed.clone(), self.value_deserialize_seed.clone())
+  }
+}
+
+
+/// Deserialize a sequence of type `C`, such as `Vec<S::Value>`, `VecDeque<S::Value>`, `HashSet<S::Value>`, or
+/// `Box<[S::Value]>`, using `deserialize_seed` to deserialize elements. Implements [`DeserializeSeed`]. Seeds compose,
+/// so nested sequences such as `Vec<Vec<Box<dyn O>>>` are deserialized by nesting this seed.
+///
+/// ```
+/// # use std::collections::VecDeque;
+/// # use std::fmt::Debug;
+/// # use serde::de::DeserializeSeed;
+/// # use serde_flexitos::{MapRegistry, Registry};
+/// # use serde_flexitos::de::{DeserializeSeqWith, DeserializeTraitObject};
+/// # trait Example: Debug {}
+/// # #[derive(serde::Deserialize, Debug)]
+/// # struct Foo(String);
+/// # impl Example for Foo {}
+/// # impl From<Foo> for Box<dyn Example> {
+/// #   fn from(value: Foo) -> Self { Box::new(value) }
+/// # }
+/// let mut registry = MapRegistry::<dyn Example>::new("Example");
+/// registry.register_type::<Foo>("Foo");
+///
+/// let seed = DeserializeSeqWith::new(DeserializeSeqWith::new(DeserializeTraitObject(&registry)));
+/// let mut deserializer = serde_json::Deserializer::from_str(r#"[[{"Foo":"A"}],[]]"#);
+/// let nested: VecDeque<Vec<Box<dyn Example>>> = seed.deserialize(&mut deserializer)?;
+/// assert_eq!(nested.len(), 2);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+pub struct DeserializeSeqWith<S, C> {
+  deserialize_seed: S,
+  collection: PhantomData<fn() -> C>,
+}
+
+impl<S, C> DeserializeSeqWith<S, C> {
+  /// Deserialize `C`, using `deserialize_seed` to deserialize elements.
+  #[inline]
+  pub fn new(deserialize_seed: S) -> Self {
+    Self { deserialize_seed, collection: PhantomData }
+  }
+}
+
+impl<'r, R: Registry, C> DeserializeSeqWith<DeserializeTraitObject<'r, R>, C> {
+  /// Deserialize a sequence of `Box<R::TraitObject>`, using `registry` to get deserialize functions for concrete types
+  /// of the trait object.
+  #[inline]
+  pub fn trait_object(registry: &'r R) -> Self {
+    Self::new(DeserializeTraitObject(registry))
+  }
+}
+
+impl<'de, S, C> DeserializeSeed<'de> for DeserializeSeqWith<S, C> where
+  S: DeserializeSeed<'de> + Clone,
+  C: FromIterator<S::Value>,
+{
+  type Value = C;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_seq(self)
+  }
+}
+
+impl<'de, S, C> Visitor<'de> for DeserializeSeqWith<S, C> where
+  S: DeserializeSeed<'de> + Clone,
+  C: FromIterator<S::Value>,
+{
+  type Value = C;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "a sequence with a custom element `DeserializeSeed` impl")
+  }
+
+  #[inline]
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let size_hint = seq.size_hint();
+    try_collect(size_hint, || seq.next_element_seed(self.deserialize_seed.clone()))
+  }
+}
+
+impl<S: Copy, C> Copy for DeserializeSeqWith<S, C> {}
+impl<S: Clone, C> Clone for DeserializeSeqWith<S, C> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self::new(self.deserialize_seed.clone())
+  }
+}
+
+
+/// Deserialize an array `[S::Value; N]`, using `S` to deserialize elements. Implements [`DeserializeSeed`].
+#[repr(transparent)]
+pub struct DeserializeArrayWith<S, const N: usize>(pub S);
+
+impl<'de, S, const N: usize> DeserializeSeed<'de> for DeserializeArrayWith<S, N> where
+  S: DeserializeSeed<'de> + Clone,
+{
+  type Value = [S::Value; N];
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_tuple(N, self)
+  }
+}
+
+impl<'de, S, const N: usize> Visitor<'de> for DeserializeArrayWith<S, N> where
+  S: DeserializeSeed<'de> + Clone,
+{
+  type Value = [S::Value; N];
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "an array of length {} with a custom element `DeserializeSeed` impl", N)
+  }
+
+  #[inline]
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let mut elements = Vec::with_capacity(N);
+    while elements.len() < N {
+      let Some(element) = seq.next_element_seed(self.0.clone())? else {
+        return Err(de::Error::invalid_length(elements.len(), &self));
+      };
+      elements.push(element);
+    }
+    match elements.try_into() {
+      Ok(array) => Ok(array),
+      Err(_) => unreachable!("exactly N elements were deserialized"),
+    }
+  }
+}
+
+impl<S: Copy, const N: usize> Copy for DeserializeArrayWith<S, N> {}
+impl<S: Clone, const N: usize> Clone for DeserializeArrayWith<S, N> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self(self.0.clone())
+  }
+}
+
+
+/// Deserialize a tuple `(S0::Value, S1::Value, ..)`, using the seeds in tuple `T = (S0, S1, ..)` to deserialize the
+/// elements. Implemented for tuples of up to 8 seeds. Implements [`DeserializeSeed`].
+#[repr(transparent)]
+pub struct DeserializeTupleWith<T>(pub T);
+
+macro_rules! impl_deserialize_tuple_with {
+  ($len:literal => $($index:literal $seed:ident $element:ident),+) => {
+    impl<'de, $($seed: DeserializeSeed<'de>),+> DeserializeSeed<'de> for DeserializeTupleWith<($($seed,)+)> {
+      type Value = ($($seed::Value,)+);
+
+      #[inline]
+      fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_tuple($len, self)
+      }
+    }
+
+    impl<'de, $($seed: DeserializeSeed<'de>),+> Visitor<'de> for DeserializeTupleWith<($($seed,)+)> {
+      type Value = ($($seed::Value,)+);
+
+      #[inline]
+      fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tuple of length {} with custom element `DeserializeSeed` impls", $len)
+      }
+
+      #[inline]
+      fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let ($($element,)+) = self.0;
+        $(
+          let Some($element) = seq.next_element_seed($element)? else {
+            return Err(de::Error::invalid_length($index, &format!("a tuple of length {}", $len).as_str()));
+          };
+        )+
+        Ok(($($element,)+))
+      }
+    }
+  };
+}
+impl_deserialize_tuple_with!(1 => 0 S0 e0);
+impl_deserialize_tuple_with!(2 => 0 S0 e0, 1 S1 e1);
+impl_deserialize_tuple_with!(3 => 0 S0 e0, 1 S1 e1, 2 S2 e2);
+impl_deserialize_tuple_with!(4 => 0 S0 e0, 1 S1 e1, 2 S2 e2, 3 S3 e3);
+impl_deserialize_tuple_with!(5 => 0 S0 e0, 1 S1 e1, 2 S2 e2, 3 S3 e3, 4 S4 e4);
+impl_deserialize_tuple_with!(6 => 0 S0 e0, 1 S1 e1, 2 S2 e2, 3 S3 e3, 4 S4 e4, 5 S5 e5);
+impl_deserialize_tuple_with!(7 => 0 S0 e0, 1 S1 e1, 2 S2 e2, 3 S3 e3, 4 S4 e4, 5 S5 e5, 6 S6 e6);
+impl_deserialize_tuple_with!(8 => 0 S0 e0, 1 S1 e1, 2 S2 e2, 3 S3 e3, 4 S4 e4, 5 S5 e5, 6 S6 e6, 7 S7 e7);
+
+impl<T: Copy> Copy for DeserializeTupleWith<T> {}
+impl<T: Clone> Clone for DeserializeTupleWith<T> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self(self.0.clone())
+  }
+}
+
+
+/// Deserialize `Option<S::Value>`, using `S` to deserialize the value if it is present. Implements [`DeserializeSeed`].
+#[repr(transparent)]
+pub struct DeserializeOptionWith<S>(pub S);
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for DeserializeOptionWith<S> {
+  type Value = Option<S::Value>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_option(self)
+  }
+}
+
+impl<'de, S: DeserializeSeed<'de>> Visitor<'de> for DeserializeOptionWith<S> {
+  type Value = Option<S::Value>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "an option with a custom `DeserializeSeed` impl")
+  }
+
+  #[inline]
+  fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+    Ok(None)
+  }
+
+  #[inline]
+  fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+    Ok(None)
+  }
+
+  #[inline]
+  fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    self.0.deserialize(deserializer).map(Some)
+  }
+}
+
+impl<S: Copy> Copy for DeserializeOptionWith<S> {}
+impl<S: Clone> Clone for DeserializeOptionWith<S> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self(self.0.clone())
+  }
+}
+
+
+/// Collect the elements returned by `next` into `C` until it returns `Ok(None)`, or return the first error. See
+/// [`try_elements`] for how capacity is preallocated.
+#[inline]
+fn try_collect<T, C: FromIterator<T>, E>(
+  size_hint: Option<usize>,
+  next: impl FnMut() -> Result<Option<T>, E>,
+) -> Result<C, E> {
+  Ok(try_elements(size_hint, next)?.into_iter().collect())
+}
+
+/// Extend `collection` with the elements returned by `next` until it returns `Ok(None)`, or return the first error. See
+/// [`try_elements`] for how capacity is preallocated.
+#[inline]
+pub(crate) fn try_extend<T, C: Extend<T>, E>(
+  collection: &mut C,
+  size_hint: Option<usize>,
+  next: impl FnMut() -> Result<Option<T>, E>,
+) -> Result<(), E> {
+  collection.extend(try_elements(size_hint, next)?);
+  Ok(())
+}
+
+/// Collect the elements returned by `next` into a `Vec` until it returns `Ok(None)`, or return the first error. The
+/// `Vec` reserves capacity for `size_hint` elements, the number of elements hinted by the deserializer, capped to not
+/// preallocate excessively for malicious input. Collections built from its exact-size iterator reserve capacity for all
+/// elements at once, and a `Vec` reuses its allocation.
+#[inline]
+fn try_elements<T, E>(size_hint: Option<usize>, mut next: impl FnMut() -> Result<Option<T>, E>) -> Result<Vec<T>, E> {
+  let mut elements = Vec::with_capacity(size_hint.unwrap_or(0).min(4096));
+  while let Some(element) = next()? {
+    elements.push(element);
+  }
+  Ok(elements)
+}
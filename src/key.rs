@@ -0,0 +1,76 @@
+//! String key representation for trait objects, for map keys in formats that only support string keys, such as JSON.
+//!
+//! [`serialize_trait_object_key`](crate::serialize_trait_object_key) serializes a trait object as a single string
+//! `id:value`, where `id` is the unique identifier of the concrete type, and `value` is the trait object encoded as a
+//! string by a [`KeyCodec`]. [`DeserializeTraitObjectKey`](crate::de::DeserializeTraitObjectKey) and
+//! [`deserialize_trait_object_key`](crate::Registry::deserialize_trait_object_key) split the string at the first
+//! [`KEY_SEPARATOR`], and decode the value with the deserialize function of the ID. Therefore, IDs may not contain
+//! [`KEY_SEPARATOR`].
+//!
+//! With the `json` feature, `JsonKeyCodec` encodes values as JSON, producing keys such as `Foo:{"name":"A"}`.
+
+use std::fmt::Display;
+
+use crate::DynDeserializeFn;
+
+/// Separator between the ID and the encoded value of a trait object key.
+pub const KEY_SEPARATOR: char = ':';
+
+/// Encodes trait objects into strings and decodes them back, for the value part of trait object keys.
+pub trait KeyCodec {
+  /// The type of encode and decode errors.
+  type Error: Display;
+
+  /// Encodes `value` into a string.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when `value` fails to serialize.
+  fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<String, Self::Error>;
+
+  /// Decodes `encoded` into a trait object with `deserialize_fn`.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when `encoded` is malformed or when `deserialize_fn` fails.
+  fn decode<O: ?Sized>(&self, encoded: &str, deserialize_fn: &DynDeserializeFn<O>) -> Result<Box<O>, Self::Error>;
+}
+
+impl<C: KeyCodec + ?Sized> KeyCodec for &C {
+  type Error = C::Error;
+
+  #[inline]
+  fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<String, Self::Error> {
+    (**self).encode(value)
+  }
+
+  #[inline]
+  fn decode<O: ?Sized>(&self, encoded: &str, deserialize_fn: &DynDeserializeFn<O>) -> Result<Box<O>, Self::Error> {
+    (**self).decode(encoded, deserialize_fn)
+  }
+}
+
+
+/// [KeyCodec] encoding values as JSON.
+#[cfg(feature = "json")]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct JsonKeyCodec;
+
+#[cfg(feature = "json")]
+impl KeyCodec for JsonKeyCodec {
+  type Error = serde_json::Error;
+
+  #[inline]
+  fn encode(&self, value: &dyn erased_serde::Serialize) -> Result<String, Self::Error> {
+    serde_json::to_string(value)
+  }
+
+  #[inline]
+  fn decode<O: ?Sized>(&self, encoded: &str, deserialize_fn: &DynDeserializeFn<O>) -> Result<Box<O>, Self::Error> {
+    let mut deserializer = serde_json::Deserializer::from_str(encoded);
+    let trait_object = deserialize_fn(&mut <dyn erased_serde::Deserializer>::erase(&mut deserializer))
+      .map_err(serde::de::Error::custom)?;
+    deserializer.end()?;
+    Ok(trait_object)
+  }
+}
@@ -63,6 +63,9 @@
 //! 1) the ID (`&'static str`) for that concrete type,
 //! 2) a [deserialize function](DeserializeFn) that deserializes the concrete type as a boxed trait object.
 //!
+//! Deserialize functions that capture state, such as a cache or an interner, can be registered with
+//! [register_closure](Registry::register_closure) instead.
+//!
 //! Traits must have [`erased_serde::Serialize`] as a supertrait and have a method to retrieve the ID of the concrete
 //! type. Concrete types of the trait must implement [`Serialize`].
 //!
@@ -169,15 +172,64 @@
 //!    know which of the deserialize impls we need to use.
 //!
 //! Whether [`Registry::get_deserialize_fn`] returns one of these errors depends on the implementation. The standard
-//! [`MapRegistry`] implementation returns these errors as a safe default. You can create your own [`Registry`]
-//! implementation if you want different behaviour. For example, a registry that ignores multiple registrations and
-//! instead chooses the first registration. See `examples/first_registration.rs` for an example of that.
+//! [`MapRegistry`] implementation returns these errors as a safe default, and records the type name and source location
+//! of each registration, to report which registrations collided. You can create your own [`Registry`] implementation if
+//! you want different behaviour. For example, a registry that ignores multiple registrations and instead chooses the
+//! first registration. See `examples/first_registration.rs` for an example of that.
 //!
 //! Finally, serialization of trait objects is fallible because serializing the concrete type behind the trait object
 //! is fallible. Additionally, serialization could fail due to the serializer not being able to serialize an ID. For
 //! example, JSON only supports maps (key-value pairs) with string keys, and would thus fail with IDs that cannot be
 //! serialized to a string.
 //!
+//! Registration does not check that the ID that a trait object is serialized with is equal to the ID that its concrete
+//! type was registered with. Use [`check::check_ids`] in a test or at startup to catch such mismatches.
+//!
+//! # Representations
+//!
+//! By default, trait objects are (de)serialized with the [externally tagged enum representation][exttag]:
+//! `{"Foo": "A"}`. The following alternative representations are supported as well, by using the corresponding
+//! serialize functions and deserialize methods on [`Registry`]:
+//!
+//! - [Internally tagged][inttag]: [`serialize_trait_object_internally_tagged`] and
+//!   [`deserialize_trait_object_internally_tagged`](Registry::deserialize_trait_object_internally_tagged) insert the ID
+//!   into the value as a field with a configurable tag name: `{"type": "Foo", "name": "A"}`. Only concrete types that
+//!   serialize as structs or maps are supported, and deserialization requires a self-describing format because the
+//!   other fields are buffered until the tag is found.
+//! - [Adjacently tagged][adjtag]: [`serialize_trait_object_adjacently_tagged`] and
+//!   [`deserialize_trait_object_adjacently_tagged`](Registry::deserialize_trait_object_adjacently_tagged) serialize a
+//!   map with configurable tag and content entry names: `{"t": "Foo", "c": {"name": "A"}}`. The tag entry is
+//!   serialized first, so this also works with formats that are not self-describing, such as bincode. Deserialization
+//!   accepts the entries in either order, but requires a self-describing format if the content entry comes first.
+//! - Tuple: [`serialize_trait_object_tuple`] and
+//!   [`deserialize_trait_object_tuple`](Registry::deserialize_trait_object_tuple) serialize an `(id, value)` tuple:
+//!   `["Foo", {"name": "A"}]`. This is more compact than a map in formats that are not self-describing, such as
+//!   [bincode][bincode].
+//! - Enum variant: [`serialize_trait_object_variant`] and
+//!   [`deserialize_trait_object_variant`](Registry::deserialize_trait_object_variant) serialize a newtype enum variant,
+//!   using the trait object name as the enum name, the ID as the variant name, and the position of the ID in the
+//!   [variants](Registry::get_variants) of the registry as the variant index. This matches the (de)serialization of
+//!   Rust enums: RON serializes `Foo(...)`, and bincode serializes the variant index instead of the ID. Only supports
+//!   `&'static str` IDs, and registries with variants, such as a [`MapRegistry`] after
+//!   [`set_variants`](MapRegistry::set_variants).
+//! - [Untagged][untag]: [`serialize_trait_object_untagged`] and
+//!   [`deserialize_trait_object_untagged`](Registry::deserialize_trait_object_untagged) serialize only the value:
+//!   `{"name": "A"}`. Deserialization buffers the value and tries deserialize functions in order until one succeeds,
+//!   which requires a self-describing format. Use this to deserialize data that has no ID at all.
+//! - String key: [`serialize_trait_object_key`] and
+//!   [`deserialize_trait_object_key`](Registry::deserialize_trait_object_key) serialize a single string `id:value`,
+//!   where a [`KeyCodec`](key::KeyCodec) encodes the value: `"Foo:{\"name\":\"A\"}"` with the `JsonKeyCodec` of the
+//!   `json` feature. Use this for trait objects in map keys of formats that only support string keys, such as JSON.
+//!
+//! Furthermore, [`serialize_trait_object_compact`] and
+//! [`deserialize_trait_object_compact`](Registry::deserialize_trait_object_compact) use the externally tagged
+//! representation, but replace the ID with a 64-bit [compact ID](compact_id) in formats that are not
+//! [human-readable](Serializer::is_human_readable), such as bincode. Human-readable formats such as JSON still contain
+//! the ID. This prevents repeating long IDs in binary data, while the compact ID remains stable when registering more
+//! types.
+//!
+//! The same representation must be used for serialization and deserialization of a trait object.
+//!
 //! # Examples
 //!
 //! Check out the examples in the `examples` directory for more use-cases:
@@ -187,18 +239,36 @@
 //!   (de)serialize that struct. This shows how trait objects can be combined/composed.
 //! - `examples/first_registration.rs`: Custom [`Registry`] implementation that ignores multiple registrations and
 //!   instead chooses the first registration
-//! - `examples/macros.rs`: Convenience macro layered on top of this crate, using [linkme][linkme] to register types.
+//! - `examples/macros.rs`: Convenience macro layered on top of this crate, using [linkme][linkme] to register types,
+//!   and [`builder::RegistryBuilder`] to report duplicate IDs at startup.
 //! - `examples/no_global.rs`: Use a local registry instead of a global one, using [`DeserializeSeed`] implementations
 //!   provided by this crate.
 //! - `examples/generic_instantiations.rs`: Create and use registries for _instantiations_ of generic traits/structs.
 //!   Does not handle traits nor structs generically though!
+//! - `examples/representations.rs`: (De)serialize trait objects with alternative representations.
+//! - `examples/context.rs`: Pass a caller-supplied context to deserialize functions, using a
+//!   [`ContextRegistry`](context::ContextRegistry).
+//! - `examples/scoped.rs`: Use a local registry with derived [`Deserialize`] impls, by installing it in a
+//!   thread-local scope.
+//! - `examples/shared.rs`: Preserve sharing of `Arc` trait objects with back-references.
+//! - `examples/type_id.rs`: Serialize trait objects without an `id` method, using the IDs registered for the
+//!   `TypeId` of concrete types.
+//! - `examples/unknown.rs`: Preserve trait objects of unregistered types so that they round-trip, or skip them in
+//!   collections. Requires the `permissive` feature.
+//! - `examples/borrowed.rs`: Deserialize trait objects that borrow from the input, using a
+//!   [`BorrowRegistry`](borrow::BorrowRegistry).
+//! - `examples/keys.rs`: Use trait objects as `HashMap` keys in JSON, with the string key representation. Requires the
+//!   `json` feature.
+//! - `examples/with.rs`: Use two registries for the same trait in one struct, with `#[serde(with = "...")]` modules
+//!   generated by the `serde_with_registry` macro.
 //!
 //! # Experimental Features
 //!
 //! This library has experimental features that are unstable and work-in-progress. Enable and use these features at your
 //! own risk.
 //!
-//! - `permissive`: [`DeserializeSeed`] and [`Visitor`] implementations for permissive deserialization.
+//! - `permissive`: [`DeserializeSeed`] and [`Visitor`](serde::de::Visitor) implementations for permissive
+//!   deserialization.
 //! - `id`: Trait, macros, and implementations for unique and stable type identifiers.
 //!
 //! # Limitations
@@ -214,12 +284,6 @@
 //! However, it is possible to register all concrete instances of types that you wish to deserialize, as is done in
 //! `example/generic_instantiations.rs`.
 //!
-//! ## Other Representations
-//!
-//! Only the [externally tagged enum representation][exttag] is supported for (de)serializing trait objects, to simplify
-//! the implementations in this crate. This is only a problem if you need to accept serialized trait objects that were
-//! serialized externally using a different representation (i.e., not this crate).
-//!
 //! # Inspiration
 //!
 //! This crate is inspired by the excellent [typetag][typetag] crate.
@@ -227,15 +291,26 @@
 //! [serde]: https://crates.io/crates/serde
 //! [erased-serde]: https://crates.io/crates/erased-serde
 //! [exttag]: https://serde.rs/enum-representations.html#externally-tagged
+//! [inttag]: https://serde.rs/enum-representations.html#internally-tagged
+//! [adjtag]: https://serde.rs/enum-representations.html#adjacently-tagged
+//! [untag]: https://serde.rs/enum-representations.html#untagged
 //! [typetag]: https://crates.io/crates/typetag
 //! [linkme]: https://crates.io/crates/linkme
+//! [bincode]: https://crates.io/crates/bincode
 //! [inventory]: https://crates.io/crates/inventory
 //! [objs]: https://doc.rust-lang.org/reference/items/traits.html#object-safety
 //! [serde_traitobject]: https://crates.io/crates/serde_traitobject
 
+use std::any::{type_name, TypeId};
+#[cfg(doc)]
+use std::any::Any;
 use std::collections::BTreeMap;
+use std::sync::OnceLock;
 use std::error::Error;
-use std::fmt::{Debug, Display, Formatter};
+use std::fmt::{self, Debug, Display, Formatter, Write};
+use std::panic::Location;
+use std::rc::Rc;
+use std::sync::Arc;
 
 use serde::de::{DeserializeOwned, DeserializeSeed, Deserializer};
 use serde::ser::{Serialize, Serializer};
@@ -243,11 +318,25 @@ use serde::Deserialize;
 
 pub mod ser;
 pub mod de;
+pub(crate) mod content;
+pub mod context;
+pub mod borrow;
+pub mod builder;
+pub mod check;
+pub mod key;
+pub mod scoped;
+pub mod shared;
+pub mod with;
 #[cfg(feature = "id_trait")]
 pub mod id;
 #[cfg(feature = "permissive")]
 pub mod permissive;
 
+#[doc(hidden)]
+pub mod __private {
+  pub use serde;
+}
+
 /// Serialize `trait_object` of type `O` with `serializer`, using `id` as the unique identifier for the concrete type of
 /// `trait_object`.
 #[inline]
@@ -263,50 +352,310 @@ pub fn serialize_trait_object<S, I, O>(
   ser::SerializeTraitObject { id, trait_object }.serialize(serializer)
 }
 
-/// Type alias for deserialize functions of trait object type `O`.
+/// Serialize `trait_object` of type `O` with `serializer` as a single id-value pair, like [`serialize_trait_object`],
+/// using the ID that was registered in `registry` for `type_id` as the unique identifier for the concrete type of
+/// `trait_object`. Types registered with [`register_type`](Registry::register_type) have their [`TypeId`] registered.
+///
+/// `type_id` must be the [`TypeId`] of the concrete type of `trait_object`. For traits with an [`Any`] supertrait, call
+/// [`type_id`](Any::type_id) on the trait object, such as `self.type_id()` in `impl Serialize for dyn Trait`, which
+/// dispatches to the concrete type. Calling it on a reference or box of the trait object instead returns the
+/// [`TypeId`] of that reference or box.
+///
+/// # Errors
+///
+/// Returns an error if no ID or multiple IDs were registered for `type_id` in `registry`, or when serialization fails.
+#[inline]
+pub fn serialize_trait_object_by_type_id<S, R, O>(
+  serializer: S,
+  registry: &R,
+  type_id: TypeId,
+  trait_object: &O,
+) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  R: Registry + ?Sized,
+  R::Identifier: Serialize,
+  O: erased_serde::Serialize + ?Sized,
+{
+  let id = registry.get_id_by_type_id(type_id).map_err(|e| match e {
+    GetError::NotRegistered { id } => serde::ser::Error::custom(format_args!(
+      "no id was registered for type id '{:?}' of a concrete type of `dyn {}`", id, registry.get_trait_object_name()
+    )),
+    GetError::MultipleRegistrations { id, registrations } => {
+      let mut message = format!(
+        "multiple ids were registered for type id '{:?}' of a concrete type of `dyn {}`",
+        id, registry.get_trait_object_name()
+      );
+      for (i, registration) in registrations.iter().enumerate() {
+        let separator = if i == 0 { ": " } else { ", " };
+        let _ = write!(message, "{}{}", separator, registration);
+      }
+      serde::ser::Error::custom(message)
+    }
+  })?;
+  ser::SerializeTraitObject { id, trait_object }.serialize(serializer)
+}
+
+/// Serialize `trait_object` of type `O` with `serializer` without an ID, using only the serialize implementation of the
+/// concrete type of `trait_object`. Deserialize with
+/// [`deserialize_trait_object_untagged`](Registry::deserialize_trait_object_untagged).
+#[inline]
+pub fn serialize_trait_object_untagged<S, O>(
+  serializer: S,
+  trait_object: &O,
+) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  O: erased_serde::Serialize + ?Sized,
+{
+  erased_serde::serialize(trait_object, serializer)
+}
+
+/// Serialize `trait_object` of type `O` with `serializer` as a single id-value pair, like [`serialize_trait_object`].
+/// However, if `serializer` is not [human-readable](Serializer::is_human_readable), the [compact ID](compact_id) of
+/// `id` is serialized instead of `id`. Deserialize with
+/// [`deserialize_trait_object_compact`](Registry::deserialize_trait_object_compact).
+#[inline]
+pub fn serialize_trait_object_compact<S, I, O>(
+  serializer: S,
+  id: I,
+  trait_object: &O,
+) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  I: Serialize + Display,
+  O: erased_serde::Serialize + ?Sized,
+{
+  ser::SerializeTraitObjectCompact { id, trait_object }.serialize(serializer)
+}
+
+/// Gets the compact ID of `id`: a 64-bit [FNV-1a] hash of the UTF-8 bytes of the [`Display`] form of `id`, used in
+/// place of `id` when serializing with [`serialize_trait_object_compact`] to formats that are not human-readable.
+///
+/// The compact ID only depends on the display form of `id`, not on [`Hash`](std::hash::Hash) implementations, and is
+/// therefore platform-independent and stable across compiler versions as long as the display form of `id` is stable.
+/// IDs with equal display forms have equal compact IDs.
+///
+/// [FNV-1a]: https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function
+pub fn compact_id<I: Display + ?Sized>(id: &I) -> u64 {
+  let mut hasher = Fnv1a(0xcbf29ce484222325);
+  write!(hasher, "{}", id).expect("writing to a hasher cannot fail");
+  hasher.0
+}
+
+/// Serialize `trait_object` of type `O` with `serializer` as an `(id, trait_object)` tuple, using `id` as the unique
+/// identifier for the concrete type of `trait_object`.
+#[inline]
+pub fn serialize_trait_object_tuple<S, I, O>(
+  serializer: S,
+  id: I,
+  trait_object: &O,
+) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  I: Serialize,
+  O: erased_serde::Serialize + ?Sized,
+{
+  ser::SerializeTraitObjectTuple { id, trait_object }.serialize(serializer)
+}
+
+/// Serialize `trait_object` of type `O` with `serializer` as a single string `id:value`, using `id` as the unique
+/// identifier for the concrete type of `trait_object`, and `codec` to encode `trait_object` as `value`. Use this for
+/// trait objects in map keys of formats that only support string keys, such as JSON. Deserialize with
+/// [`deserialize_trait_object_key`](Registry::deserialize_trait_object_key).
+///
+/// # Errors
+///
+/// Returns an error when `id` contains [`key::KEY_SEPARATOR`], or when `codec` fails to encode `trait_object`.
+#[inline]
+pub fn serialize_trait_object_key<S, I, O, C>(
+  serializer: S,
+  id: I,
+  trait_object: &O,
+  codec: C,
+) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  I: Display,
+  O: erased_serde::Serialize + ?Sized,
+  C: key::KeyCodec,
+{
+  ser::SerializeTraitObjectKey { id, trait_object, codec }.serialize(serializer)
+}
+
+/// Serialize `trait_object` of type `O` with `serializer` as a newtype enum variant, using `id` as the unique
+/// identifier for the concrete type of `trait_object`. The trait object name of `registry` is used as the enum name,
+/// `id` as the variant name, and the position of `id` in the [variants](Registry::get_variants) of `registry` as the
+/// variant index.
+///
+/// Formats that serialize enum variants by index, such as [bincode](https://crates.io/crates/bincode), only serialize
+/// the variant index. Like the variants of a Rust enum, data remains readable as long as variants are only appended.
+///
+/// # Errors
+///
+/// Returns an error if [`registry.get_deserialize_fn(id)`](Registry::get_deserialize_fn) returns an error, if `id` is
+/// not one of the [variants](Registry::get_variants) of `registry`, or when serialization fails.
+pub fn serialize_trait_object_variant<S, R, O>(
+  serializer: S,
+  registry: &R,
+  id: &'static str,
+  trait_object: &O,
+) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  R: Registry<Identifier=&'static str> + ?Sized,
+  O: erased_serde::Serialize + ?Sized,
+{
+  let name = registry.get_trait_object_name();
+  if let Err(e) = registry.get_deserialize_fn(id) {
+    return Err(serde::ser::Error::custom(format_args!("cannot serialize `{}` variant '{}': {}", name, id, e)));
+  }
+  let Some(variant_index) = registry.get_variants().iter().position(|variant| *variant == id) else {
+    return Err(serde::ser::Error::custom(format_args!(
+      "cannot serialize `{}` variant '{}': not one of the variants of the registry", name, id
+    )));
+  };
+  let variant_index = variant_index as u32;
+  ser::SerializeTraitObjectVariant { name, variant_index, variant: id, trait_object }.serialize(serializer)
+}
+
+/// Serialize `trait_object` of type `O` with `serializer` as a map with a `tag` entry containing `id` as the unique
+/// identifier for the concrete type of `trait_object`, and a `content` entry containing `trait_object`.
+#[inline]
+pub fn serialize_trait_object_adjacently_tagged<S, I, O>(
+  serializer: S,
+  tag: &'static str,
+  content: &'static str,
+  id: I,
+  trait_object: &O,
+) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  I: Serialize,
+  O: erased_serde::Serialize + ?Sized,
+{
+  ser::SerializeTraitObjectAdjacentlyTagged { tag, content, id, trait_object }.serialize(serializer)
+}
+
+/// Serialize `trait_object` of type `O` with `serializer`, inserting `id` as the unique identifier for the concrete
+/// type of `trait_object` into it as a `tag` field. See [`SerializeTraitObjectInternallyTagged`] for which concrete
+/// types are supported.
+///
+/// [`SerializeTraitObjectInternallyTagged`]: ser::SerializeTraitObjectInternallyTagged
+#[inline]
+pub fn serialize_trait_object_internally_tagged<S, I, O>(
+  serializer: S,
+  tag: &'static str,
+  id: I,
+  trait_object: &O,
+) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  I: Serialize,
+  O: erased_serde::Serialize + ?Sized,
+{
+  ser::SerializeTraitObjectInternallyTagged { tag, id, trait_object }.serialize(serializer)
+}
+
+/// Type alias for deserialize functions of trait object type `O`. As `O` does not depend on `'de`, deserialized trait
+/// objects cannot borrow from the input. Use the [borrow] module for trait objects that borrow from the input.
 pub type DeserializeFn<O> = for<'de> fn(&mut dyn erased_serde::Deserializer<'de>) -> Result<Box<O>, erased_serde::Error>;
 
+/// Type alias for dynamic deserialize functions of trait object type `O`, such as closures capturing state. Every
+/// [`DeserializeFn`] is also a dynamic deserialize function.
+pub type DynDeserializeFn<O> =
+  dyn for<'de> Fn(&mut dyn erased_serde::Deserializer<'de>) -> Result<Box<O>, erased_serde::Error> + Send + Sync;
+
 /// Registry mapping unique identifiers of types to their deserialize implementations, enabling deserialization of a
 /// specific trait object type.
 pub trait Registry {
   /// The type of unique identifiers this registry uses. `&'static str` is used as a default.
   type Identifier;
   /// The trait object type this registry maps deserialize functions for.
-  type TraitObject: ?Sized;
+  type TraitObject: ?Sized + 'static;
 
   /// Register `deserialize_fn` as the deserialize function for `id`.
   ///
   /// This method is infallible, but errors such as multiple registrations for `id` may be propagated to
   /// deserialization-time by making [get_deserialize_fn](Self::get_deserialize_fn) return an error.
-  fn register(&mut self, id: Self::Identifier, deserialize_fn: DeserializeFn<Self::TraitObject>);
+  #[inline]
+  #[track_caller]
+  fn register(&mut self, id: Self::Identifier, deserialize_fn: DeserializeFn<Self::TraitObject>) {
+    self.register_boxed(id, Box::new(deserialize_fn));
+  }
+
+  /// Register dynamic `deserialize_fn` as the deserialize function for `id`. Unlike [register](Self::register), the
+  /// deserialize function may capture state, such as a cache or an interner.
+  ///
+  /// This method is infallible, but errors such as multiple registrations for `id` may be propagated to
+  /// deserialization-time by making [get_deserialize_fn](Self::get_deserialize_fn) return an error.
+  ///
+  /// Implementations may record a [`RegistrationInfo`] with the location of the caller, as this method tracks its
+  /// caller.
+  #[track_caller]
+  fn register_boxed(&mut self, id: Self::Identifier, deserialize_fn: Box<DynDeserializeFn<Self::TraitObject>>);
+
+  /// Register dynamic `deserialize_fn` as the deserialize function for `id`, like
+  /// [register_boxed](Self::register_boxed), with diagnostic information `info` about the registration.
+  /// [register_type](Self::register_type) calls this method with the name of the registered type. Implementations may
+  /// record `info`, to report which types were registered where when multiple deserialize functions are registered for
+  /// the same ID.
+  ///
+  /// This method is infallible, but errors such as multiple registrations for `id` may be propagated to
+  /// deserialization-time by making [get_deserialize_fn](Self::get_deserialize_fn) return an error. The default
+  /// implementation ignores `info` and calls [register_boxed](Self::register_boxed).
+  #[inline]
+  fn register_boxed_with_info(
+    &mut self,
+    id: Self::Identifier,
+    info: RegistrationInfo,
+    deserialize_fn: Box<DynDeserializeFn<Self::TraitObject>>,
+  ) {
+    let _ = info;
+    self.register_boxed(id, deserialize_fn);
+  }
+
+  /// Register closure `deserialize_fn` as the deserialize function for `id`, like
+  /// [register_boxed](Self::register_boxed).
+  ///
+  /// This method is infallible, but errors such as multiple registrations for `id` may be propagated to
+  /// deserialization-time by making [get_deserialize_fn](Self::get_deserialize_fn) return an error.
+  #[inline]
+  #[track_caller]
+  fn register_closure<F>(&mut self, id: Self::Identifier, deserialize_fn: F) where
+    F: for<'de> Fn(&mut dyn erased_serde::Deserializer<'de>) -> Result<Box<Self::TraitObject>, erased_serde::Error>,
+    F: Send + Sync + 'static,
+    Self: Sized,
+  {
+    self.register_boxed(id, Box::new(deserialize_fn));
+  }
 
-  /// Register a default deserialize function for type `T` as the deserialize function for `id`. `T` must implement
-  /// [`DeserializeOwned`] and must be convertable into [`Box<Self::TraitObject>`] with
+  /// Register a default deserialize function for type `T` as the deserialize function for `id`, and register `id` as
+  /// the ID of the [`TypeId`] of `T` with [register_type_id_with_info](Self::register_type_id_with_info). `T` must
+  /// implement [`DeserializeOwned`] and must be convertable into [`Box<Self::TraitObject>`] with
   /// [`Into<Box<Self::TraitObject>>`].
   ///
   /// This method is infallible, but errors such as multiple registrations for `id` may be propagated to
   /// deserialization-time by making [get_deserialize_fn](Self::get_deserialize_fn) return an error.
   #[inline]
+  #[track_caller]
   fn register_type<T>(&mut self, id: Self::Identifier) where
-    T: DeserializeOwned + Into<Box<Self::TraitObject>>,
+    T: DeserializeOwned + Into<Box<Self::TraitObject>> + 'static,
+    Self::Identifier: Clone,
   {
-    self.register(id, |d| {
+    let info = RegistrationInfo::new(Some(type_name::<T>()));
+    self.register_type_id_with_info(TypeId::of::<T>(), id.clone(), info);
+    self.register_boxed_with_info(id, info, Box::new(|d| {
       let deserialized = erased_serde::deserialize::<T>(d)?;
       let boxed = deserialized.into();
       Ok(boxed)
-    });
+    }));
   }
 
-  /// Register a default deserialize function for type `T` as the deserialize function for [`T::ID`]. `T` must implement
-  /// [`Id`](id::Id) and [`DeserializeOwned`], and must be convertable into [`Box<Self::TraitObject>`] with
-  /// [`Into<Box<Self::TraitObject>>`].
+  /// Register a default deserialize function for type `T` as the deserialize function for [`T::ID`](id::Id::ID). `T`
+  /// must implement [`Id`](id::Id) and [`DeserializeOwned`], and must be convertable into [`Box<Self::TraitObject>`]
+  /// with [`Into<Box<Self::TraitObject>>`].
   ///
   /// This method is infallible, but errors such as multiple registrations for `T::ID` may be propagated to
   /// deserialization-time by making [get_deserialize_fn](Self::get_deserialize_fn) return an error.
   #[cfg(feature = "id_trait")]
   #[inline]
+  #[track_caller]
   fn register_id_type<T>(&mut self) where
-    T: id::Id<Self::Identifier> + DeserializeOwned + Into<Box<Self::TraitObject>>,
+    T: id::Id<Self::Identifier> + DeserializeOwned + Into<Box<Self::TraitObject>> + 'static,
+    Self::Identifier: Clone,
   {
     self.register_type::<T>(T::ID);
   }
@@ -327,6 +676,194 @@ pub trait Registry {
     de::DeserializeTraitObject(self).deserialize(deserializer)
   }
 
+  /// Deserialize a trait object into an [`Arc`] with `deserializer`, like
+  /// [deserialize_trait_object](Self::deserialize_trait_object).
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when [get_deserialize_fn](Self::get_deserialize_fn) returns an error for the deserialized ID, or
+  /// when deserialization fails.
+  #[inline]
+  fn deserialize_trait_object_arc<'de, D>(&self, deserializer: D) -> Result<Arc<Self::TraitObject>, D::Error> where
+    D: Deserializer<'de>,
+    Self: Sized,
+    Self::Identifier: Deserialize<'de> + Debug,
+  {
+    de::DeserializeTraitObjectArc(self).deserialize(deserializer)
+  }
+
+  /// Deserialize a trait object into an [`Rc`] with `deserializer`, like
+  /// [deserialize_trait_object](Self::deserialize_trait_object).
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when [get_deserialize_fn](Self::get_deserialize_fn) returns an error for the deserialized ID, or
+  /// when deserialization fails.
+  #[inline]
+  fn deserialize_trait_object_rc<'de, D>(&self, deserializer: D) -> Result<Rc<Self::TraitObject>, D::Error> where
+    D: Deserializer<'de>,
+    Self: Sized,
+    Self::Identifier: Deserialize<'de> + Debug,
+  {
+    de::DeserializeTraitObjectRc(self).deserialize(deserializer)
+  }
+
+  /// Deserialize a shared trait object into an [`Arc`] with `deserializer`, restoring back-references to trait objects
+  /// previously deserialized with `context` as clones of the same [`Arc`]. See the [shared] module for more info.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when [get_deserialize_fn](Self::get_deserialize_fn) returns an error for the deserialized ID,
+  /// when a back-reference was not deserialized before with `context`, when a trait object is deserialized for a
+  /// reference that was already deserialized with `context`, or when deserialization fails.
+  #[inline]
+  fn deserialize_trait_object_shared<'de, D>(
+    &self,
+    context: &shared::DeserializeContext<Self::TraitObject>,
+    deserializer: D,
+  ) -> Result<Arc<Self::TraitObject>, D::Error> where
+    D: Deserializer<'de>,
+    Self: Sized,
+    Self::Identifier: Deserialize<'de> + Debug,
+  {
+    shared::DeserializeShared { registry: self, context }.deserialize(deserializer)
+  }
+
+  /// Deserialize a trait object with `deserializer` from a value without an ID, by trying the deserialize functions of
+  /// all IDs from [get_ids](Self::get_ids) in order, until one succeeds. The value is buffered in order to try multiple
+  /// deserialize functions, which requires a self-describing format. Use
+  /// [`DeserializeTraitObjectUntagged`](de::DeserializeTraitObjectUntagged) to only try an ordered list of candidates.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when none of the deserialize functions succeed, listing the error of each tried ID, including IDs
+  /// for which [get_deserialize_fn](Self::get_deserialize_fn) returns an error.
+  #[inline]
+  fn deserialize_trait_object_untagged<'de, D>(&self, deserializer: D) -> Result<Box<Self::TraitObject>, D::Error> where
+    D: Deserializer<'de>,
+    Self: Sized,
+    Self::Identifier: Clone + Debug,
+  {
+    de::DeserializeTraitObjectUntagged { registry: self, candidates: None }.deserialize(deserializer)
+  }
+
+  /// Deserialize a trait object with `deserializer` from a single id-value pair, like
+  /// [deserialize_trait_object](Self::deserialize_trait_object). However, if `deserializer` is not
+  /// [human-readable](Deserializer::is_human_readable), the [compact ID](compact_id) is deserialized instead of the ID,
+  /// and [get_deserialize_fn_by_compact_id](Self::get_deserialize_fn_by_compact_id) is used to get the deserialize
+  /// function.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when getting the deserialize function returns an error for the deserialized (compact) ID, or when
+  /// deserialization fails.
+  #[inline]
+  fn deserialize_trait_object_compact<'de, D>(&self, deserializer: D) -> Result<Box<Self::TraitObject>, D::Error> where
+    D: Deserializer<'de>,
+    Self: Sized,
+    Self::Identifier: Deserialize<'de> + Debug + Display + Clone,
+  {
+    de::DeserializeTraitObjectCompact(self).deserialize(deserializer)
+  }
+
+  /// Deserialize a trait object with `deserializer` from an `(id, value)` tuple, using this registry to get the
+  /// deserialize function for the concrete type, based on the deserialized ID.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when [get_deserialize_fn](Self::get_deserialize_fn) returns an error for the deserialized ID, or
+  /// when deserialization fails.
+  #[inline]
+  fn deserialize_trait_object_tuple<'de, D>(&self, deserializer: D) -> Result<Box<Self::TraitObject>, D::Error> where
+    D: Deserializer<'de>,
+    Self: Sized,
+    Self::Identifier: Deserialize<'de> + Debug,
+  {
+    de::DeserializeTraitObjectTuple(self).deserialize(deserializer)
+  }
+
+  /// Deserialize a trait object with `deserializer` from a single string `id:value`, using this registry to get the
+  /// deserialize function for the concrete type, based on the ID, and `codec` to decode the value with it.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when the string does not contain [`key::KEY_SEPARATOR`], when
+  /// [get_deserialize_fn](Self::get_deserialize_fn) returns an error for the ID, or when `codec` fails to decode the
+  /// value.
+  #[inline]
+  fn deserialize_trait_object_key<'de, D, C>(
+    &self,
+    codec: C,
+    deserializer: D,
+  ) -> Result<Box<Self::TraitObject>, D::Error> where
+    D: Deserializer<'de>,
+    C: key::KeyCodec,
+    Self: Sized,
+    Self::Identifier: Display + Clone,
+  {
+    de::DeserializeTraitObjectKey { registry: self, codec }.deserialize(deserializer)
+  }
+
+  /// Deserialize a trait object with `deserializer` from a map with a `tag` field containing the ID, using this
+  /// registry to get the deserialize function for the concrete type, based on the deserialized ID. The remaining fields
+  /// of the map are deserialized with that deserialize function.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when the `tag` field is missing, when [get_deserialize_fn](Self::get_deserialize_fn) returns an
+  /// error for the deserialized ID, or when deserialization fails.
+  #[inline]
+  fn deserialize_trait_object_internally_tagged<'de, D>(
+    &self,
+    tag: &'static str,
+    deserializer: D,
+  ) -> Result<Box<Self::TraitObject>, D::Error> where
+    D: Deserializer<'de>,
+    Self: Sized,
+    Self::Identifier: Deserialize<'de> + Debug,
+  {
+    de::DeserializeTraitObjectInternallyTagged { registry: self, tag }.deserialize(deserializer)
+  }
+
+  /// Deserialize a trait object with `deserializer` from a map with a `tag` entry containing the ID and a `content`
+  /// entry containing the value, using this registry to get the deserialize function for the concrete type, based on
+  /// the deserialized ID. The entries may occur in either order.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when the `tag` or `content` entry is missing, when
+  /// [get_deserialize_fn](Self::get_deserialize_fn) returns an error for the deserialized ID, or when deserialization
+  /// fails.
+  #[inline]
+  fn deserialize_trait_object_adjacently_tagged<'de, D>(
+    &self,
+    tag: &'static str,
+    content: &'static str,
+    deserializer: D,
+  ) -> Result<Box<Self::TraitObject>, D::Error> where
+    D: Deserializer<'de>,
+    Self: Sized,
+    Self::Identifier: Deserialize<'de> + Debug,
+  {
+    de::DeserializeTraitObjectAdjacentlyTagged { registry: self, tag, content }.deserialize(deserializer)
+  }
+
+  /// Deserialize a trait object with `deserializer` from a newtype enum variant, where the variant name or index
+  /// identifies the concrete type, using this registry to get the deserialize function for the concrete type. See
+  /// [`serialize_trait_object_variant`] for how variant names and indices are assigned.
+  ///
+  /// # Errors
+  ///
+  /// Returns an error when the variant is not one of the [variants](Self::get_variants) or a valid index, when
+  /// [get_deserialize_fn](Self::get_deserialize_fn) returns an error for the ID, or when deserialization fails.
+  #[inline]
+  fn deserialize_trait_object_variant<'de, D>(&self, deserializer: D) -> Result<Box<Self::TraitObject>, D::Error> where
+    D: Deserializer<'de>,
+    Self: Registry<Identifier=&'static str> + Sized,
+  {
+    de::DeserializeTraitObjectVariant(self).deserialize(deserializer)
+  }
+
   /// Gets the deserialize function for `id`.
   ///
   /// # Errors
@@ -334,68 +871,369 @@ pub trait Registry {
   /// Implementations may return the following errors:
   ///
   /// - `GetError::NotRegistered { id }` if no deserialize function was registered for `id`.
-  /// - `GetError::MultipleRegistrations { id }` if multiple deserialize functions were registered for `id`.
-  fn get_deserialize_fn(&self, id: Self::Identifier) -> Result<&DeserializeFn<Self::TraitObject>, GetError<Self::Identifier>>;
+  /// - `GetError::MultipleRegistrations { id, registrations }` if multiple deserialize functions were registered for
+  ///   `id`. `registrations` has the recorded [`RegistrationInfo`] of each of them, if the implementation records it.
+  fn get_deserialize_fn(
+    &self,
+    id: Self::Identifier,
+  ) -> Result<&DynDeserializeFn<Self::TraitObject>, GetError<Self::Identifier>>;
+
+  /// Gets the deserialize function for the ID with [compact ID](compact_id) `compact_id`.
+  ///
+  /// The default implementation gets the compact ID of every ID from [get_ids](Self::get_ids), and then gets the
+  /// deserialize function of the matching ID. Implementations may override this with a more efficient lookup.
+  ///
+  /// # Errors
+  ///
+  /// Implementations may return the following errors:
+  ///
+  /// - `GetError::NotRegistered { id }` if no deserialize function was registered for an ID with `compact_id`.
+  /// - `GetError::MultipleRegistrations { id, .. }` if multiple deserialize functions were registered for the ID with
+  ///   `compact_id`, or if multiple registered IDs have `compact_id`.
+  fn get_deserialize_fn_by_compact_id(
+    &self,
+    compact_id: u64,
+  ) -> Result<&DynDeserializeFn<Self::TraitObject>, GetError<u64>> where
+    Self::Identifier: Display + Clone,
+  {
+    let mut ids = self.get_ids().filter(|id| crate::compact_id(*id) == compact_id);
+    match (ids.next(), ids.next()) {
+      (None, _) => Err(GetError::NotRegistered { id: compact_id }),
+      (Some(_), Some(_)) => Err(GetError::MultipleRegistrations { id: compact_id, registrations: Vec::new() }),
+      (Some(id), None) => self.get_deserialize_fn(id.clone()).map_err(|e| e.with_id(compact_id)),
+    }
+  }
+
+  /// Gets the variant names of the [enum variant representation](serialize_trait_object_variant), where the variant
+  /// index of a variant is its position. Like the variants of a Rust enum, these are passed to
+  /// [`Deserializer::deserialize_enum`].
+  ///
+  /// Implementations should return the same variants in the same order for the lifetime of serialized data, as formats
+  /// such as bincode only serialize the variant index. The default implementation returns no variants, meaning that the
+  /// enum variant representation is unsupported.
+  #[inline]
+  fn get_variants(&self) -> &'static [&'static str] {
+    &[]
+  }
+
+  /// Register `id` as the ID of the concrete type with `type_id`, for serialization with
+  /// [`serialize_trait_object_by_type_id`]. [register_type](Self::register_type) calls this method.
+  ///
+  /// This method is infallible, but errors such as registering different IDs for `type_id` may be propagated to
+  /// serialization-time by making [get_id_by_type_id](Self::get_id_by_type_id) return an error. The default
+  /// implementation does nothing.
+  ///
+  /// Implementations may record a [`RegistrationInfo`] with the location of the caller, as this method tracks its
+  /// caller.
+  #[inline]
+  #[track_caller]
+  fn register_type_id(&mut self, type_id: TypeId, id: Self::Identifier) {
+    let _ = (type_id, id);
+  }
+
+  /// Register `id` as the ID of the concrete type with `type_id`, like [register_type_id](Self::register_type_id), with
+  /// diagnostic information `info` about the registration. [register_type](Self::register_type) calls this method with
+  /// the name of the registered type. Implementations may record `info`, to report which types were registered where
+  /// when different IDs are registered for the same `type_id`.
+  ///
+  /// This method is infallible, but errors such as registering different IDs for `type_id` may be propagated to
+  /// serialization-time by making [get_id_by_type_id](Self::get_id_by_type_id) return an error. The default
+  /// implementation ignores `info` and calls [register_type_id](Self::register_type_id).
+  #[inline]
+  fn register_type_id_with_info(&mut self, type_id: TypeId, id: Self::Identifier, info: RegistrationInfo) {
+    let _ = info;
+    self.register_type_id(type_id, id);
+  }
+
+  /// Gets the ID of the concrete type with `type_id`.
+  ///
+  /// # Errors
+  ///
+  /// Implementations may return the following errors:
+  ///
+  /// - `GetError::NotRegistered { id }` if no ID was registered for `type_id`.
+  /// - `GetError::MultipleRegistrations { id, registrations }` if different IDs were registered for `type_id`.
+  ///   `registrations` has the recorded [`RegistrationInfo`] of each of them, if the implementation records it.
+  ///
+  /// The default implementation always returns `GetError::NotRegistered { id }`.
+  #[inline]
+  fn get_id_by_type_id(&self, type_id: TypeId) -> Result<&Self::Identifier, GetError<TypeId>> {
+    Err(GetError::NotRegistered { id: type_id })
+  }
+
+  /// Gets the IDs that deserialize functions were registered for, including IDs with multiple registrations.
+  ///
+  /// Implementations should return the IDs in a stable order: the same set of registered IDs should always result in
+  /// the same order, regardless of the order of registration.
+  ///
+  /// The default implementation returns no IDs, meaning that introspection is unsupported.
+  #[inline]
+  fn get_ids(&self) -> Box<dyn Iterator<Item=&Self::Identifier> + '_> {
+    Box::new(std::iter::empty())
+  }
+
+  /// Returns whether a deserialize function was registered for `id`, including when multiple deserialize functions were
+  /// registered for `id`.
+  ///
+  /// The default implementation searches [get_ids](Self::get_ids). Implementations may override this with a more
+  /// efficient lookup.
+  #[inline]
+  fn contains(&self, id: &Self::Identifier) -> bool where
+    Self::Identifier: PartialEq,
+  {
+    self.get_ids().any(|registered_id| registered_id == id)
+  }
+
+  /// Gets the number of IDs that deserialize functions were registered for, including IDs with multiple registrations.
+  ///
+  /// The default implementation counts [get_ids](Self::get_ids). Implementations may override this with a more
+  /// efficient implementation.
+  #[inline]
+  fn len(&self) -> usize {
+    self.get_ids().count()
+  }
+
+  /// Returns whether no deserialize functions were registered.
+  #[inline]
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Gets the IDs that multiple deserialize functions were registered for, for which
+  /// [get_deserialize_fn](Self::get_deserialize_fn) returns `GetError::MultipleRegistrations`, in the order of
+  /// [get_ids](Self::get_ids). Use this to fail at startup instead of at deserialization-time.
+  ///
+  /// The default implementation calls [get_deserialize_fn](Self::get_deserialize_fn) for every ID from
+  /// [get_ids](Self::get_ids). Implementations may override this with a more efficient implementation.
+  #[inline]
+  fn get_ids_with_multiple_registrations(&self) -> Box<dyn Iterator<Item=&Self::Identifier> + '_> where
+    Self::Identifier: Clone,
+  {
+    Box::new(self.get_ids().filter(|id| {
+      matches!(self.get_deserialize_fn((*id).clone()), Err(GetError::MultipleRegistrations { .. }))
+    }))
+  }
 
   /// Gets the trait object name, for diagnostic purposes.
   fn get_trait_object_name(&self) -> &'static str;
 }
 
+/// 64-bit FNV-1a hasher over the UTF-8 bytes of written strings.
+struct Fnv1a(u64);
+
+impl fmt::Write for Fnv1a {
+  #[inline]
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    for byte in s.bytes() {
+      self.0 ^= byte as u64;
+      self.0 = self.0.wrapping_mul(0x100000001b3);
+    }
+    Ok(())
+  }
+}
+
+
 /// Error while getting deserialize function.
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
 pub enum GetError<I> {
   /// No deserialize function was registered for `id`.
   NotRegistered { id: I },
-  /// Multiple deserialize functions were registered for `id`.
-  MultipleRegistrations { id: I },
+  /// Multiple deserialize functions were registered for `id`. `registrations` has the recorded [`RegistrationInfo`] of
+  /// each of them in order of registration, or is empty if the registry does not record them.
+  MultipleRegistrations { id: I, registrations: Vec<RegistrationInfo> },
+}
+impl<I> GetError<I> {
+  /// Returns this error with `id` as the ID.
+  #[inline]
+  pub fn with_id<J>(self, id: J) -> GetError<J> {
+    match self {
+      GetError::NotRegistered { .. } => GetError::NotRegistered { id },
+      GetError::MultipleRegistrations { registrations, .. } => GetError::MultipleRegistrations { id, registrations },
+    }
+  }
 }
 impl<I: Debug> Error for GetError<I> {}
 impl<I: Debug> Display for GetError<I> {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     match self {
       GetError::NotRegistered { id } => write!(f, "no deserialize function was registered for id '{:?}'", id),
-      GetError::MultipleRegistrations { id } => write!(f, "multiple deserialize functions were registered for id '{:?}'", id),
+      GetError::MultipleRegistrations { id, registrations } => {
+        write!(f, "multiple deserialize functions were registered for id '{:?}'", id)?;
+        for (i, registration) in registrations.iter().enumerate() {
+          let separator = if i == 0 { ": " } else { ", " };
+          write!(f, "{}{}", separator, registration)?;
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+/// Diagnostic information about the registration of a deserialize function.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+pub struct RegistrationInfo {
+  /// Name of the concrete type, as returned by [`type_name`], or `None` if unknown, such as for deserialize functions
+  /// registered with [register](Registry::register).
+  pub type_name: Option<&'static str>,
+  /// Source location of the registration.
+  pub location: &'static Location<'static>,
+}
+impl RegistrationInfo {
+  /// Creates registration info for the concrete type named `type_name`, with the location of the caller.
+  #[inline]
+  #[track_caller]
+  pub fn new(type_name: Option<&'static str>) -> Self {
+    Self { type_name, location: Location::caller() }
+  }
+}
+impl Display for RegistrationInfo {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self.type_name {
+      Some(type_name) => write!(f, "`{}` at {}", type_name, self.location),
+      None => write!(f, "unknown type at {}", self.location),
     }
   }
 }
 
 
 /// [Registry] implementation mapping unique identifiers of type `I` to deserialize functions of trait object type `O`,
-/// using a [BTreeMap].
+/// using a [BTreeMap]. [get_ids](Registry::get_ids) returns IDs in ascending order.
 pub struct MapRegistry<O: ?Sized, I = &'static str> {
-  deserialize_fns: BTreeMap<I, Option<DeserializeFn<O>>>,
+  /// Registrations by ID. Multiple registrations if multiple deserialize functions were registered for the same ID.
+  deserialize_fns: BTreeMap<I, Vec<Registration<O>>>,
+  /// Registrations by compact ID. Multiple registrations if multiple deserialize functions were registered for the
+  /// same compact ID. Built on the first lookup by compact ID, as only that requires `I: Display`, and reset on
+  /// registration.
+  compact_deserialize_fns: OnceLock<BTreeMap<u64, Vec<Registration<O>>>>,
+  /// IDs by type ID of concrete types, with diagnostic information. Multiple IDs if IDs were registered multiple times
+  /// for the same type ID.
+  ids_by_type_id: BTreeMap<TypeId, Vec<(I, RegistrationInfo)>>,
+  /// Variant names of the enum variant representation, in variant index order.
+  variants: &'static [&'static str],
   trait_object_name: &'static str,
 }
 
+/// Registered deserialize function of a [MapRegistry], along with its diagnostic information.
+struct Registration<O: ?Sized> {
+  info: RegistrationInfo,
+  deserialize_fn: Arc<DynDeserializeFn<O>>,
+}
+
+impl<O: ?Sized> Clone for Registration<O> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Self { info: self.info, deserialize_fn: self.deserialize_fn.clone() }
+  }
+}
+
 impl<O: ?Sized, I> MapRegistry<O, I> {
   /// Creates a new registry, using `trait_object_name` as the name of `O` for diagnostic purposes.
   #[inline]
   pub fn new(trait_object_name: &'static str) -> Self {
     Self {
       deserialize_fns: BTreeMap::new(),
+      compact_deserialize_fns: OnceLock::new(),
+      ids_by_type_id: BTreeMap::new(),
+      variants: &[],
       trait_object_name,
     }
   }
+
+  /// Sets the variant names of the [enum variant representation](serialize_trait_object_variant) to `variants`, where
+  /// the variant index of a variant is its position in `variants`. Variants without a registered deserialize function
+  /// fail to deserialize.
+  ///
+  /// Treat `variants` like the variants of a Rust enum: only append new variants, as reordering or removing variants
+  /// changes the variant indices of serialized data.
+  #[inline]
+  pub fn set_variants(&mut self, variants: &'static [&'static str]) {
+    self.variants = variants;
+  }
 }
 
-impl<O: ?Sized, I: Ord> Registry for MapRegistry<O, I> {
+impl<O: ?Sized + 'static, I: Ord> Registry for MapRegistry<O, I> {
   type Identifier = I;
   type TraitObject = O;
 
   #[inline]
-  fn register(&mut self, id: I, deserialize_fn: DeserializeFn<O>) {
-    self.deserialize_fns.entry(id)
-      .and_modify(|v| { v.take(); })
-      .or_insert_with(|| Some(deserialize_fn));
+  fn register_boxed(&mut self, id: I, deserialize_fn: Box<DynDeserializeFn<O>>) {
+    self.register_boxed_with_info(id, RegistrationInfo::new(None), deserialize_fn);
   }
 
   #[inline]
-  fn get_deserialize_fn(&self, id: I) -> Result<&DeserializeFn<O>, GetError<I>> {
-    match self.deserialize_fns.get(&id) {
-      None => Err(GetError::NotRegistered { id }),
-      Some(None) => Err(GetError::MultipleRegistrations { id }),
-      Some(Some(deserialize_fn)) => Ok(deserialize_fn),
-    }
+  fn register_boxed_with_info(&mut self, id: I, info: RegistrationInfo, deserialize_fn: Box<DynDeserializeFn<O>>) {
+    self.compact_deserialize_fns.take();
+    let registration = Registration { info, deserialize_fn: deserialize_fn.into() };
+    self.deserialize_fns.entry(id).or_default().push(registration);
+  }
+
+  #[inline]
+  fn get_deserialize_fn(&self, id: I) -> Result<&DynDeserializeFn<O>, GetError<I>> {
+    let registrations = self.deserialize_fns.get(&id).map_or(&[][..], Vec::as_slice);
+    get_single_registration(registrations, id, |registration| (registration.info, registration.deserialize_fn.as_ref()))
+  }
+
+  #[inline]
+  fn get_deserialize_fn_by_compact_id(&self, compact_id: u64) -> Result<&DynDeserializeFn<O>, GetError<u64>> where
+    I: Display + Clone,
+  {
+    let compact_deserialize_fns = self.compact_deserialize_fns.get_or_init(|| {
+      let mut compact_deserialize_fns = BTreeMap::new();
+      for (id, registrations) in &self.deserialize_fns {
+        compact_deserialize_fns.entry(crate::compact_id(id))
+          .or_insert_with(Vec::new)
+          .extend(registrations.iter().cloned());
+      }
+      compact_deserialize_fns
+    });
+    let registrations = compact_deserialize_fns.get(&compact_id).map_or(&[][..], Vec::as_slice);
+    get_single_registration(registrations, compact_id, |registration| {
+      (registration.info, registration.deserialize_fn.as_ref())
+    })
+  }
+
+  #[inline]
+  fn get_variants(&self) -> &'static [&'static str] {
+    self.variants
+  }
+
+  #[inline]
+  fn register_type_id(&mut self, type_id: TypeId, id: I) {
+    self.register_type_id_with_info(type_id, id, RegistrationInfo::new(None));
+  }
+
+  #[inline]
+  fn register_type_id_with_info(&mut self, type_id: TypeId, id: I, info: RegistrationInfo) {
+    self.ids_by_type_id.entry(type_id).or_default().push((id, info));
+  }
+
+  #[inline]
+  fn get_id_by_type_id(&self, type_id: TypeId) -> Result<&I, GetError<TypeId>> {
+    get_single_id(self.ids_by_type_id.get(&type_id).map_or(&[], Vec::as_slice), type_id)
+  }
+
+  #[inline]
+  fn get_ids(&self) -> Box<dyn Iterator<Item=&I> + '_> {
+    Box::new(self.deserialize_fns.keys())
+  }
+
+  #[inline]
+  fn contains(&self, id: &I) -> bool where
+    I: PartialEq,
+  {
+    self.deserialize_fns.contains_key(id)
+  }
+
+  #[inline]
+  fn len(&self) -> usize {
+    self.deserialize_fns.len()
+  }
+
+  #[inline]
+  fn get_ids_with_multiple_registrations(&self) -> Box<dyn Iterator<Item=&I> + '_> where
+    I: Clone,
+  {
+    Box::new(self.deserialize_fns.iter().filter_map(|(id, registrations)| (registrations.len() > 1).then_some(id)))
   }
 
   #[inline]
@@ -403,3 +1241,46 @@ impl<O: ?Sized, I: Ord> Registry for MapRegistry<O, I> {
     self.trait_object_name
   }
 }
+
+
+/// Gets the deserialize function of the single registration in `registrations` for `id`, using `project` to get the
+/// diagnostic information and deserialize function of a registration.
+///
+/// # Errors
+///
+/// Returns an error if `registrations` is empty or has multiple registrations.
+#[inline]
+pub(crate) fn get_single_registration<'r, R, F: ?Sized, I>(
+  registrations: &'r [R],
+  id: I,
+  project: impl Fn(&'r R) -> (RegistrationInfo, &'r F),
+) -> Result<&'r F, GetError<I>> {
+  match registrations {
+    [] => Err(GetError::NotRegistered { id }),
+    [registration] => Ok(project(registration).1),
+    registrations => Err(GetError::MultipleRegistrations {
+      id,
+      registrations: registrations.iter().map(|registration| project(registration).0).collect(),
+    }),
+  }
+}
+
+/// Gets the single ID of `registrations` of IDs for `type_id`, allowing the same ID to be registered multiple times.
+///
+/// # Errors
+///
+/// Returns an error if `registrations` is empty or has different IDs.
+#[inline]
+pub(crate) fn get_single_id<I: PartialEq>(
+  registrations: &[(I, RegistrationInfo)],
+  type_id: TypeId,
+) -> Result<&I, GetError<TypeId>> {
+  let Some(((id, _), others)) = registrations.split_first() else {
+    return Err(GetError::NotRegistered { id: type_id });
+  };
+  if others.iter().any(|(other, _)| other != id) {
+    let registrations = registrations.iter().map(|(_, info)| *info).collect();
+    return Err(GetError::MultipleRegistrations { id: type_id, registrations });
+  }
+  Ok(id)
+}
@@ -1,16 +1,21 @@
 //! Experimental/work-in-progress [`DeserializeSeed`] and [`Visitor`] implementations for permissive deserialization of
 //! trait objects and collections of trait objects. Instead of returning an error, permissive deserialization returns
 //! `None` or skips adding a trait object to a collection, when no deserialize function is registered for a concrete type.
+//! Skipped values are consumed, and collections report how many elements or entries were skipped with [`Skipped`].
+//! Alternatively, [`PreservingDeserializeTraitObject`] preserves such trait objects as [`Unknown`] values.
 
+use std::error::Error;
 use std::fmt::{self, Debug, Display, Formatter};
+use std::marker::PhantomData;
 
-use serde::de::{self, Deserializer, DeserializeSeed, MapAccess, Visitor};
-use serde::Deserialize;
+use serde::de::{self, DeserializeOwned, Deserializer, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
 
-use crate::{DeserializeFn, GetError, Registry};
-use crate::de::DeserializeWithFn;
+use crate::{DynDeserializeFn, GetError, Registry};
+use crate::content::{Content, ContentDeserializer};
+use crate::de::{try_extend, DeserializeWithFn};
 
-/// Deserialize [`Option<Box<<R as Registry>::TraitObject>>`] from a single id-value pair, using the registry to get
+/// Deserialize `Option<Box<<R as Registry>::TraitObject>>` from a single id-value pair, using the registry to get
 /// deserialize functions for concrete types of the trait object.  Returns `None` if no deserialize function was found.
 /// Implements [`DeserializeSeed`].
 #[repr(transparent)]
@@ -40,11 +45,12 @@ impl<'de, R: Registry> Visitor<'de> for PermissiveDeserializeTraitObject<'_, R>
   #[inline]
   fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
     let Some(deserialize_fn) = map.next_key_seed(PermissiveIdToDeserializeFn(self.0))? else {
-      return Err(de::Error::custom(&self));
+      return Err(de::Error::custom(self));
     };
     let value = if let Some(deserialize_fn) = deserialize_fn {
       Some(map.next_value_seed(DeserializeWithFn(deserialize_fn))?)
     } else {
+      map.next_value::<IgnoredAny>()?;
       None
     };
     Ok(value)
@@ -64,20 +70,492 @@ impl<'de, R: Registry> Display for PermissiveDeserializeTraitObject<'_, R> where
 }
 
 
+/// Deserialize [`Option<Box<<R as Registry>::TraitObject>>`](Self::Value) from a single id-value pair, like
+/// [`PermissiveDeserializeTraitObject`], but reports skipped and failed trait objects to `diagnostics`. Implements
+/// [`DeserializeSeed`].
+pub struct PermissiveDeserializeTraitObjectWithDiagnostics<'r, R, D> {
+  pub registry: &'r R,
+  pub diagnostics: D,
+}
+
+impl<'de, R: Registry, D> DeserializeSeed<'de> for PermissiveDeserializeTraitObjectWithDiagnostics<'_, R, D> where
+  R::Identifier: Deserialize<'de> + Clone + Debug,
+  D: Diagnostics<R::Identifier>,
+{
+  type Value = Option<Box<R::TraitObject>>;
+
+  #[inline]
+  fn deserialize<De>(self, deserializer: De) -> Result<Self::Value, De::Error> where De: Deserializer<'de> {
+    deserializer.deserialize_map(self)
+  }
+}
+
+impl<'de, R: Registry, D> Visitor<'de> for PermissiveDeserializeTraitObjectWithDiagnostics<'_, R, D> where
+  R::Identifier: Deserialize<'de> + Clone + Debug,
+  D: Diagnostics<R::Identifier>,
+{
+  type Value = Option<Box<R::TraitObject>>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    PermissiveDeserializeTraitObject(self.registry).expecting(formatter)
+  }
+
+  #[inline]
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+    let Some(id) = map.next_key::<R::Identifier>()? else {
+      return Err(de::Error::custom(self));
+    };
+    let trait_object_name = self.registry.get_trait_object_name();
+    match self.registry.get_deserialize_fn(id.clone()) {
+      Ok(deserialize_fn) => map.next_value_seed(DeserializeWithFn(deserialize_fn))
+        .map(Some)
+        .inspect_err(|e| self.diagnostics.report(trait_object_name, &id, Reason::Deserialize(e))),
+      Err(e @ GetError::NotRegistered { .. }) => {
+        self.diagnostics.report(trait_object_name, &id, Reason::Get(&e));
+        map.next_value::<IgnoredAny>()?;
+        Ok(None)
+      }
+      Err(e) => {
+        self.diagnostics.report(trait_object_name, &id, Reason::Get(&e));
+        Err(de::Error::custom(e))
+      }
+    }
+  }
+}
+
+impl<R, D: Copy> Copy for PermissiveDeserializeTraitObjectWithDiagnostics<'_, R, D> {}
+impl<R, D: Clone> Clone for PermissiveDeserializeTraitObjectWithDiagnostics<'_, R, D> {
+  #[inline]
+  fn clone(&self) -> Self { Self { registry: self.registry, diagnostics: self.diagnostics.clone() } }
+}
+impl<'de, R: Registry, D> Display for PermissiveDeserializeTraitObjectWithDiagnostics<'_, R, D> where
+  R::Identifier: Deserialize<'de> + Clone + Debug,
+  D: Diagnostics<R::Identifier>,
+{
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.expecting(f) }
+}
+
+
+/// Receives skipped and failed trait objects from permissive deserialization, for example to log them or to collect
+/// metrics about unknown concrete types. Implemented for `()`, which does nothing, and for closures taking the same
+/// arguments as [`report`](Self::report).
+pub trait Diagnostics<I> {
+  /// Reports that the trait object with `id`, of trait object type with name `trait_object_name`, was skipped or failed
+  /// to deserialize because of `reason`.
+  fn report(&self, trait_object_name: &'static str, id: &I, reason: Reason<'_, I>);
+}
+
+impl<I> Diagnostics<I> for () {
+  #[inline]
+  fn report(&self, _trait_object_name: &'static str, _id: &I, _reason: Reason<'_, I>) {}
+}
+
+impl<I, F> Diagnostics<I> for F where
+  F: Fn(&'static str, &I, Reason<'_, I>),
+{
+  #[inline]
+  fn report(&self, trait_object_name: &'static str, id: &I, reason: Reason<'_, I>) {
+    self(trait_object_name, id, reason)
+  }
+}
+
+/// Reason a trait object was skipped or failed to deserialize, reported to [`Diagnostics`].
+#[derive(Copy, Clone, Debug)]
+pub enum Reason<'a, I> {
+  /// Getting the deserialize function failed. The trait object was skipped if the error is
+  /// [`GetError::NotRegistered`], and failed otherwise.
+  Get(&'a GetError<I>),
+  /// Deserializing the value of the trait object with its deserialize function failed.
+  Deserialize(&'a dyn Error),
+}
+
+impl<I: Debug> Display for Reason<'_, I> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    match self {
+      Reason::Get(e) => Display::fmt(e, f),
+      Reason::Deserialize(e) => Display::fmt(e, f),
+    }
+  }
+}
+
+
+/// Value of a permissive collection deserialization, along with the number of elements or entries that were skipped
+/// because no deserialize function was registered for their concrete type.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Skipped<T> {
+  pub value: T,
+  pub skipped: usize,
+}
+
+
+/// Deserialize [`Vec<Box<<R as Registry>::TraitObject>>`](Skipped::value), using the registry to get deserialize
+/// functions for concrete types of the trait object. Elements for which no deserialize function was registered are
+/// skipped and counted in [`Skipped::skipped`]. Implements [`DeserializeSeed`].
+///
+/// Reports skipped and failed trait objects to `diagnostics`, which does nothing by default.
+pub struct PermissiveDeserializeVecWithTraitObject<'r, R, D = ()> {
+  pub registry: &'r R,
+  pub diagnostics: D,
+}
+
+impl<'r, R> PermissiveDeserializeVecWithTraitObject<'r, R> {
+  /// Creates a seed that deserializes vectors of trait objects using `registry`, without diagnostics.
+  #[inline]
+  pub fn new(registry: &'r R) -> Self {
+    Self { registry, diagnostics: () }
+  }
+}
+
+impl<'r, R, D> PermissiveDeserializeVecWithTraitObject<'r, R, D> {
+  /// Creates a seed that deserializes vectors of trait objects using `registry`, reporting skipped and failed trait
+  /// objects to `diagnostics`.
+  #[inline]
+  pub fn with_diagnostics(registry: &'r R, diagnostics: D) -> Self {
+    Self { registry, diagnostics }
+  }
+
+  #[inline]
+  fn element(&self) -> PermissiveDeserializeTraitObjectWithDiagnostics<'r, R, D> where D: Clone {
+    PermissiveDeserializeTraitObjectWithDiagnostics { registry: self.registry, diagnostics: self.diagnostics.clone() }
+  }
+}
+
+impl<'de, R: Registry, D> DeserializeSeed<'de> for PermissiveDeserializeVecWithTraitObject<'_, R, D> where
+  R::Identifier: Deserialize<'de> + Clone + Debug,
+  D: Diagnostics<R::Identifier> + Clone,
+{
+  type Value = Skipped<Vec<Box<R::TraitObject>>>;
+
+  #[inline]
+  fn deserialize<De: Deserializer<'de>>(self, deserializer: De) -> Result<Self::Value, De::Error> {
+    deserializer.deserialize_seq(self)
+  }
+}
+
+impl<'de, R: Registry, D> Visitor<'de> for PermissiveDeserializeVecWithTraitObject<'_, R, D> where
+  R::Identifier: Deserialize<'de> + Clone + Debug,
+  D: Diagnostics<R::Identifier> + Clone,
+{
+  type Value = Skipped<Vec<Box<R::TraitObject>>>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    formatter.write_str("a sequence of '")?;
+    self.element().expecting(formatter)?;
+    formatter.write_str("'")
+  }
+
+  #[inline]
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let mut vec = if let Some(capacity) = seq.size_hint() {
+      Vec::with_capacity(capacity)
+    } else {
+      Vec::new()
+    };
+    let mut skipped = 0;
+    while let Some(trait_object) = seq.next_element_seed(self.element())? {
+      if let Some(trait_object) = trait_object {
+        vec.push(trait_object);
+      } else {
+        skipped += 1;
+      }
+    }
+    Ok(Skipped { value: vec, skipped })
+  }
+}
+
+impl<R, D: Copy> Copy for PermissiveDeserializeVecWithTraitObject<'_, R, D> {}
+impl<R, D: Clone> Clone for PermissiveDeserializeVecWithTraitObject<'_, R, D> {
+  #[inline]
+  fn clone(&self) -> Self { Self { registry: self.registry, diagnostics: self.diagnostics.clone() } }
+}
+
+
+/// Deserialize map `M` with keys `K` and values `V`, using `key_deserialize_seed` to deserialize `Option<K>`, and
+/// `value_deserialize_seed` to deserialize `Option<V>`. Entries where either the key or value is `None` are skipped and
+/// counted in [`Skipped::skipped`]. When the key is `None`, the value is ignored. `M` can be any map that implements
+/// [`Default`] and [`Extend`], such as a `HashMap` with a custom hasher or a `BTreeMap`, and is usually inferred from
+/// the type of the deserialized value. Like [`DeserializeMapWith`](crate::de::DeserializeMapWith), `M` preallocates
+/// capacity from the size hint of the deserializer if its [`Extend`] implementation reserves capacity. Implements
+/// [`DeserializeSeed`]. Use the following functions to create instances of this struct:
+/// - [trait_object_key](Self::trait_object_key): permissively deserialize map keys as trait objects,
+/// - [trait_object_value](Self::trait_object_value): permissively deserialize map values as trait objects,
+/// - [trait_object_key_and_value](Self::trait_object_key_and_value): permissively deserialize map keys and values as
+///   trait objects.
+/// - [new](Self::new): deserialize map keys and values with custom seeds.
+pub struct PermissiveDeserializeMapWith<K, V, M> {
+  key_deserialize_seed: K,
+  value_deserialize_seed: V,
+  map: PhantomData<fn() -> M>,
+}
+
+impl<K, V, M> PermissiveDeserializeMapWith<K, V, M> {
+  /// Deserialize map `M` with custom key and value seeds, such as [`PermissiveDeserializeTraitObjectWithDiagnostics`],
+  /// or [`Required`] for keys or values that are not deserialized permissively.
+  #[inline]
+  pub fn new(key_deserialize_seed: K, value_deserialize_seed: V) -> Self {
+    Self { key_deserialize_seed, value_deserialize_seed, map: PhantomData }
+  }
+}
+
+impl<'k, K, V, R, M> PermissiveDeserializeMapWith<
+  PermissiveDeserializeTraitObject<'k, R>,
+  Required<PhantomData<V>>,
+  M,
+> where
+  K: ?Sized,
+  R: Registry<TraitObject=K>
+{
+  /// Deserialize a map with `Box<K>` keys and `V` values, permissively deserializing `Box<K>` as a trait object where
+  /// `K` is the trait object type, using `registry` to get deserialize functions for concrete types of trait object
+  /// `K`.
+  #[inline]
+  pub fn trait_object_key(registry: &'k R) -> Self {
+    Self {
+      key_deserialize_seed: PermissiveDeserializeTraitObject(registry),
+      value_deserialize_seed: Required(PhantomData),
+      map: PhantomData,
+    }
+  }
+}
+
+impl<'v, K, V, R, M> PermissiveDeserializeMapWith<
+  Required<PhantomData<K>>,
+  PermissiveDeserializeTraitObject<'v, R>,
+  M,
+> where
+  V: ?Sized,
+  R: Registry<TraitObject=V>
+{
+  /// Deserialize a map with `K` keys and `Box<V>` values, permissively deserializing `Box<V>` as a trait object where
+  /// `V` is the trait object type, using `registry` to get deserialize functions for concrete types of trait object
+  /// `V`.
+  #[inline]
+  pub fn trait_object_value(registry: &'v R) -> Self {
+    Self {
+      key_deserialize_seed: Required(PhantomData),
+      value_deserialize_seed: PermissiveDeserializeTraitObject(registry),
+      map: PhantomData,
+    }
+  }
+}
+
+impl<'k, 'v, K, RK, V, RV, M> PermissiveDeserializeMapWith<
+  PermissiveDeserializeTraitObject<'k, RK>,
+  PermissiveDeserializeTraitObject<'v, RV>,
+  M,
+> where
+  K: ?Sized,
+  V: ?Sized,
+  RK: Registry<TraitObject=K>,
+  RV: Registry<TraitObject=V>
+{
+  /// Deserialize a map with `Box<K>` keys and `Box<V>` values:
+  /// - permissively deserialize `Box<K>` as a trait object where `K` is the trait object type, using `key_registry` to
+  ///   get deserialize functions for concrete types of trait object `K`.
+  /// - permissively deserialize `Box<V>` as a trait object where `V` is the trait object type, using `value_registry`
+  ///   to get deserialize functions for concrete types of trait object `V`.
+  #[inline]
+  pub fn trait_object_key_and_value(key_registry: &'k RK, value_registry: &'v RV) -> Self {
+    Self {
+      key_deserialize_seed: PermissiveDeserializeTraitObject(key_registry),
+      value_deserialize_seed: PermissiveDeserializeTraitObject(value_registry),
+      map: PhantomData,
+    }
+  }
+}
+
+impl<'de, K, KV, V, VV, M> DeserializeSeed<'de> for PermissiveDeserializeMapWith<K, V, M> where
+  K: DeserializeSeed<'de, Value=Option<KV>> + Clone,
+  V: DeserializeSeed<'de, Value=Option<VV>> + Clone,
+  M: Default + Extend<(KV, VV)>,
+{
+  type Value = Skipped<M>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_map(self)
+  }
+}
+
+impl<'de, K, KV, V, VV, M> Visitor<'de> for PermissiveDeserializeMapWith<K, V, M> where
+  K: DeserializeSeed<'de, Value=Option<KV>> + Clone,
+  V: DeserializeSeed<'de, Value=Option<VV>> + Clone,
+  M: Default + Extend<(KV, VV)>,
+{
+  type Value = Skipped<M>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "a map with custom permissive key and value `DeserializeSeed` impls")
+  }
+
+  #[inline]
+  fn visit_map<A: MapAccess<'de>>(self, mut map_access: A) -> Result<Self::Value, A::Error> {
+    let mut map = M::default();
+    let mut skipped = 0;
+    try_extend(&mut map, map_access.size_hint(), || {
+      while let Some(key) = map_access.next_key_seed(self.key_deserialize_seed.clone())? {
+        let Some(key) = key else {
+          map_access.next_value::<IgnoredAny>()?;
+          skipped += 1;
+          continue;
+        };
+        match map_access.next_value_seed(self.value_deserialize_seed.clone())? {
+          Some(value) => return Ok(Some((key, value))),
+          None => skipped += 1,
+        }
+      }
+      Ok(None)
+    })?;
+    Ok(Skipped { value: map, skipped })
+  }
+}
+
+
+/// Deserialize `Some(S::Value)` using `S`, for keys or values of [`PermissiveDeserializeMapWith`] that are not
+/// deserialized permissively. Implements [`DeserializeSeed`].
+#[derive(Copy, Clone)]
+#[repr(transparent)]
+pub struct Required<S>(pub S);
+
+impl<'de, S: DeserializeSeed<'de>> DeserializeSeed<'de> for Required<S> {
+  type Value = Option<S::Value>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    self.0.deserialize(deserializer).map(Some)
+  }
+}
+
+
+/// Deserialize [`Box<<R as Registry>::TraitObject>`](Self::Value) from a single id-value pair, using the registry to
+/// get deserialize functions for concrete types of the trait object. If no deserialize function was registered for the
+/// ID, the ID and value are preserved as [`Unknown<I>`] instead, which is converted into the trait object with
+/// [`Into<Box<<R as Registry>::TraitObject>>`](Into). Implements [`DeserializeSeed`].
+///
+/// The ID is buffered to deserialize it as both `R::Identifier` and `I`, and the value of an unknown trait object is
+/// buffered, which only works with self-describing formats.
+pub struct PreservingDeserializeTraitObject<'r, R, I = String> {
+  registry: &'r R,
+  human_readable: bool,
+  _unknown_id: PhantomData<fn() -> I>,
+}
+
+impl<'r, R, I> PreservingDeserializeTraitObject<'r, R, I> {
+  /// Creates a seed that deserializes trait objects using `registry`, preserving unknown trait objects as
+  /// [`Unknown<I>`].
+  #[inline]
+  pub fn new(registry: &'r R) -> Self {
+    Self { registry, human_readable: true, _unknown_id: PhantomData }
+  }
+}
+
+impl<'de, R: Registry, I> DeserializeSeed<'de> for PreservingDeserializeTraitObject<'_, R, I> where
+  R::Identifier: Deserialize<'de> + Debug,
+  I: DeserializeOwned,
+  Unknown<I>: Into<Box<R::TraitObject>>,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error> where D: Deserializer<'de> {
+    // Deserialize buffered IDs the same as they would be deserialized from `deserializer`.
+    let human_readable = deserializer.is_human_readable();
+    deserializer.deserialize_map(Self { human_readable, ..self })
+  }
+}
+
+impl<'de, R: Registry, I> Visitor<'de> for PreservingDeserializeTraitObject<'_, R, I> where
+  R::Identifier: Deserialize<'de> + Debug,
+  I: DeserializeOwned,
+  Unknown<I>: Into<Box<R::TraitObject>>,
+{
+  type Value = Box<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "an id-value pair for `Box<dyn {}>`", self.registry.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+    let Some(id) = map.next_key::<Content>()? else {
+      return Err(de::Error::custom(self));
+    };
+    let deserialize_fn = PermissiveIdToDeserializeFn(self.registry)
+      .deserialize(ContentDeserializer::<A::Error>::new(id.clone(), self.human_readable))?;
+    if let Some(deserialize_fn) = deserialize_fn {
+      map.next_value_seed(DeserializeWithFn(deserialize_fn))
+    } else {
+      let id = I::deserialize(ContentDeserializer::<A::Error>::new(id, self.human_readable))?;
+      let value = map.next_value::<Content>()?.into_owned();
+      Ok(Unknown { id, value }.into())
+    }
+  }
+}
+
+impl<R, I> Copy for PreservingDeserializeTraitObject<'_, R, I> {}
+impl<R, I> Clone for PreservingDeserializeTraitObject<'_, R, I> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+impl<'de, R: Registry, I> Display for PreservingDeserializeTraitObject<'_, R, I> where
+  R::Identifier: Deserialize<'de> + Debug,
+  I: DeserializeOwned,
+  Unknown<I>: Into<Box<R::TraitObject>>,
+{
+  #[inline]
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.expecting(f) }
+}
+
+
+/// Trait object of a concrete type that has no registered deserialize function, preserving its ID and value so that it
+/// can be serialized again without losing data.
+///
+/// Implement your trait for [`Unknown`], returning [`id`](Unknown::id) as the ID of the concrete type, and serialize it
+/// like any other trait object. [`Unknown`] serializes the preserved value, so the serialized ID-value pair matches the
+/// deserialized one.
+///
+/// The ID of an [`Unknown`] is only known at runtime. Therefore, traits whose ID method returns `&'static str` cannot
+/// be implemented for [`Unknown`], as it cannot return its ID with a `'static` lifetime. Instead, return `&str`
+/// borrowing from `self`, or an owned ID type. The value is buffered, and can only be accessed by serializing it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Unknown<I = String> {
+  id: I,
+  value: Content<'static>,
+}
+
+impl<I> Unknown<I> {
+  /// Gets the ID of the unknown concrete type.
+  #[inline]
+  pub fn id(&self) -> &I { &self.id }
+}
+
+impl<I> Serialize for Unknown<I> {
+  #[inline]
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    self.value.serialize(serializer)
+  }
+}
+
+
 /// Deserialize [`<R as Registry>::Identifier`] and use it to get its deserialize function from the registry, returning
 /// `None` if no deserialize function was registered.
 #[repr(transparent)]
 struct PermissiveIdToDeserializeFn<'r, R>(&'r R);
 
-impl<'de, R: Registry> DeserializeSeed<'de> for PermissiveIdToDeserializeFn<'_, R> where
+impl<'de, 'r, R: Registry> DeserializeSeed<'de> for PermissiveIdToDeserializeFn<'r, R> where
   R::Identifier: Deserialize<'de> + Debug,
 {
-  type Value = Option<DeserializeFn<R::TraitObject>>;
+  type Value = Option<&'r DynDeserializeFn<R::TraitObject>>;
 
   #[inline]
   fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
     let id = R::Identifier::deserialize(deserializer)?;
-    match self.0.get_deserialize_fn(id).copied() {
+    match self.0.get_deserialize_fn(id) {
       Ok(v) => Ok(Some(v)),
       Err(GetError::NotRegistered { .. }) => Ok(None),
       Err(e) => Err(de::Error::custom(e)),
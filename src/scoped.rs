@@ -0,0 +1,95 @@
+//! Scoped thread-local registries, making local registries available to plain [`Deserialize`] impls, such as those of
+//! `#[derive(Deserialize)]` structs containing trait objects.
+//!
+//! Install a registry for the duration of a call with [`with_registry`], and look it up in the [`Deserialize`] impl of
+//! `Box<dyn Trait>` with [`with_scoped_registry`]:
+//!
+//! ```
+//! # use std::fmt::Debug;
+//! # use serde::{Deserialize, Deserializer};
+//! # use serde_flexitos::{MapRegistry, Registry};
+//! use serde_flexitos::scoped::{with_registry, with_scoped_registry};
+//!
+//! trait Example: Debug {}
+//!
+//! #[derive(Deserialize, Debug)]
+//! struct Foo(String);
+//! impl Example for Foo {}
+//! impl From<Foo> for Box<dyn Example> {
+//!   fn from(value: Foo) -> Self { Box::new(value) }
+//! }
+//!
+//! impl<'de> Deserialize<'de> for Box<dyn Example> {
+//!   fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+//!     with_scoped_registry(|registry: &MapRegistry<dyn Example>| registry.deserialize_trait_object(deserializer))
+//!   }
+//! }
+//!
+//! let mut registry = MapRegistry::<dyn Example>::new("Example");
+//! registry.register_type::<Foo>("Foo");
+//! let examples: Vec<Box<dyn Example>> = with_registry(&registry, || serde_json::from_str(r#"[{"Foo":"A"}]"#))?;
+//! # Ok::<(), serde_json::Error>(())
+//! ```
+//!
+//! Registries are identified by their type, so only one registry per type is in scope at a time. Nested calls of
+//! [`with_registry`] with a registry of the same type shadow the outer registry until they return.
+
+use std::any::{type_name, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use serde::de;
+#[cfg(doc)]
+use serde::Deserialize;
+
+thread_local! {
+  /// Registries in scope, by the type ID of the registry type, as type-erased pointers.
+  static REGISTRIES: RefCell<HashMap<TypeId, *const ()>> = RefCell::new(HashMap::new());
+}
+
+/// Calls `f` with `registry` in scope on this thread, making it available to [`with_scoped_registry`] until `f`
+/// returns (or panics).
+#[inline]
+pub fn with_registry<R: 'static, T>(registry: &R, f: impl FnOnce() -> T) -> T {
+  let type_id = TypeId::of::<R>();
+  let previous = REGISTRIES.with_borrow_mut(|registries| registries.insert(type_id, registry as *const R as *const ()));
+  let _restore = Restore { type_id, previous };
+  f()
+}
+
+/// Calls `f` with the registry of type `R` that is in scope on this thread, returning its result.
+///
+/// # Errors
+///
+/// Returns an error when no registry of type `R` is in scope, or when `f` returns an error.
+#[inline]
+pub fn with_scoped_registry<R: 'static, T, E: de::Error>(f: impl FnOnce(&R) -> Result<T, E>) -> Result<T, E> {
+  let Some(registry) = REGISTRIES.with_borrow(|registries| registries.get(&TypeId::of::<R>()).copied()) else {
+    return Err(E::custom(format_args!("no registry of type `{}` is in scope; use `with_registry`", type_name::<R>())));
+  };
+  // SAFETY: the pointer was created from a `&R` by a call of `with_registry` on this thread that has not returned yet,
+  // as `Restore` removes the pointer (or restores the pointer it shadowed) when that call returns or unwinds. That call
+  // keeps the registry borrowed, so the registry outlives this call of `with_scoped_registry`.
+  //
+  // The reference does not outlive this call either: `f` is higher-ranked over the lifetime of the reference
+  // (`for<'a> FnOnce(&'a R) -> Result<T, E>`), so `f` must work for a lifetime that ends when `f` returns. It therefore
+  // cannot return the reference, as `T` and `E` cannot name that lifetime, nor store it in captured state or anywhere
+  // else, as that would require the lifetime to outlive the storage.
+  let registry = unsafe { &*(registry as *const R) };
+  f(registry)
+}
+
+/// Restores the previous registry in scope when dropped.
+struct Restore {
+  type_id: TypeId,
+  previous: Option<*const ()>,
+}
+impl Drop for Restore {
+  #[inline]
+  fn drop(&mut self) {
+    REGISTRIES.with_borrow_mut(|registries| match self.previous {
+      Some(previous) => registries.insert(self.type_id, previous),
+      None => registries.remove(&self.type_id),
+    });
+  }
+}
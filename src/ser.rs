@@ -1,6 +1,10 @@
-//! [`Serialize`] implementation for serialization of trait objects.
+//! [`Serialize`] implementations for serialization of trait objects.
 
-use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::fmt::Display;
+
+use serde::ser::{self, Impossible, Serialize, SerializeMap, SerializeStruct, SerializeTuple, Serializer};
+
+use crate::key::{KeyCodec, KEY_SEPARATOR};
 
 /// Serialize `trait_object` as a single `id`-`trait_object` pair where `id` is the unique identifier for the concrete
 /// type of `trait_object`
@@ -9,31 +13,346 @@ pub struct SerializeTraitObject<'o, I, O: ?Sized> {
   pub trait_object: &'o O,
 }
 
-impl<'a, I, O> Serialize for SerializeTraitObject<'_, I, O> where
+impl<I, O> Serialize for SerializeTraitObject<'_, I, O> where
   I: Serialize,
-  O: ?Sized + erased_serde::Serialize + 'a
+  O: ?Sized + erased_serde::Serialize,
 {
   #[inline]
   fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
     S: Serializer
   {
-    /// Wrapper so we can implement [`Serialize`] for `Wrap(O)`.
-    #[repr(transparent)]
-    struct Wrap<'a, O: ?Sized>(&'a O);
-    impl<'a, O> Serialize for Wrap<'a, O> where
-      O: ?Sized + erased_serde::Serialize + 'a
-    {
-      #[inline]
-      fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        erased_serde::serialize(self.0, serializer)
-      }
-    }
-
     let mut map = serializer.serialize_map(Some(1))?;
     map.serialize_entry(&self.id, &Wrap(self.trait_object))?;
     map.end()
   }
 }
 
+
+/// Serialize `trait_object` as a single `id`-`trait_object` pair where `id` is the unique identifier for the concrete
+/// type of `trait_object`. If the serializer is not human-readable, the [compact ID](crate::compact_id) of `id` is
+/// serialized instead of `id`.
+pub struct SerializeTraitObjectCompact<'o, I, O: ?Sized> {
+  pub id: I,
+  pub trait_object: &'o O,
+}
+
+impl<I, O> Serialize for SerializeTraitObjectCompact<'_, I, O> where
+  I: Serialize + Display,
+  O: ?Sized + erased_serde::Serialize,
+{
+  #[inline]
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+    S: Serializer
+  {
+    if serializer.is_human_readable() {
+      SerializeTraitObject { id: &self.id, trait_object: self.trait_object }.serialize(serializer)
+    } else {
+      let compact_id = crate::compact_id(&self.id);
+      SerializeTraitObject { id: compact_id, trait_object: self.trait_object }.serialize(serializer)
+    }
+  }
+}
+
+
+/// Serialize `trait_object` as an `(id, trait_object)` tuple where `id` is the unique identifier for the concrete type
+/// of `trait_object`. More compact than a single id-value pair in formats that are not self-describing.
+pub struct SerializeTraitObjectTuple<'o, I, O: ?Sized> {
+  pub id: I,
+  pub trait_object: &'o O,
+}
+
+impl<I, O> Serialize for SerializeTraitObjectTuple<'_, I, O> where
+  I: Serialize,
+  O: ?Sized + erased_serde::Serialize,
+{
+  #[inline]
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+    S: Serializer
+  {
+    let mut tuple = serializer.serialize_tuple(2)?;
+    tuple.serialize_element(&self.id)?;
+    tuple.serialize_element(&Wrap(self.trait_object))?;
+    tuple.end()
+  }
+}
+
+
+/// Serialize `trait_object` as a newtype variant `variant` with index `variant_index` of an enum named `name`.
+pub struct SerializeTraitObjectVariant<'o, O: ?Sized> {
+  pub name: &'static str,
+  pub variant_index: u32,
+  pub variant: &'static str,
+  pub trait_object: &'o O,
+}
+
+impl<O> Serialize for SerializeTraitObjectVariant<'_, O> where
+  O: ?Sized + erased_serde::Serialize,
+{
+  #[inline]
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+    S: Serializer
+  {
+    serializer.serialize_newtype_variant(self.name, self.variant_index, self.variant, &Wrap(self.trait_object))
+  }
+}
+
+
+/// Serialize `trait_object` as a map with two entries: a `tag` entry with `id` as the unique identifier for the
+/// concrete type of `trait_object`, and a `content` entry with `trait_object`. Also known as the adjacently tagged
+/// representation.
+pub struct SerializeTraitObjectAdjacentlyTagged<'o, I, O: ?Sized> {
+  pub tag: &'static str,
+  pub content: &'static str,
+  pub id: I,
+  pub trait_object: &'o O,
+}
+
+impl<I, O> Serialize for SerializeTraitObjectAdjacentlyTagged<'_, I, O> where
+  I: Serialize,
+  O: ?Sized + erased_serde::Serialize,
+{
+  #[inline]
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+    S: Serializer
+  {
+    let mut map = serializer.serialize_map(Some(2))?;
+    map.serialize_entry(self.tag, &self.id)?;
+    map.serialize_entry(self.content, &Wrap(self.trait_object))?;
+    map.end()
+  }
+}
+
+
+/// Serialize `trait_object` with `id` as the unique identifier for the concrete type of `trait_object` inserted into
+/// it as a `tag` field, also known as the internally tagged representation.
+///
+/// Only concrete types that serialize as a struct, map, unit, or unit struct (or as a newtype struct around one of
+/// those) are supported, because other values have no fields to insert the tag into. Unit and newtype enum variants are
+/// serialized as a map with the variant as the second entry.
+///
+/// Serializing a struct with a field named `tag` fails, as the tag would clash with that field. Keys of maps are not
+/// checked for clashes with the tag.
+pub struct SerializeTraitObjectInternallyTagged<'o, I, O: ?Sized> {
+  pub tag: &'static str,
+  pub id: I,
+  pub trait_object: &'o O,
+}
+
+impl<I, O> Serialize for SerializeTraitObjectInternallyTagged<'_, I, O> where
+  I: Serialize,
+  O: ?Sized + erased_serde::Serialize,
+{
+  #[inline]
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+    S: Serializer
+  {
+    erased_serde::serialize(self.trait_object, TaggedSerializer { tag: self.tag, id: &self.id, delegate: serializer })
+  }
+}
+
+/// Serializer that inserts a `tag`-`id` field into the value it serializes, before serializing with `delegate`.
+struct TaggedSerializer<'i, I, S> {
+  tag: &'static str,
+  id: &'i I,
+  delegate: S,
+}
+
+impl<I: Serialize, S: Serializer> TaggedSerializer<'_, I, S> {
+  #[inline]
+  fn unsupported(what: impl Display) -> S::Error {
+    ser::Error::custom(format_args!("cannot serialize {} as an internally tagged trait object", what))
+  }
+}
+
+impl<I: Serialize, S: Serializer> Serializer for TaggedSerializer<'_, I, S> {
+  type Ok = S::Ok;
+  type Error = S::Error;
+  type SerializeSeq = Impossible<S::Ok, S::Error>;
+  type SerializeTuple = Impossible<S::Ok, S::Error>;
+  type SerializeTupleStruct = Impossible<S::Ok, S::Error>;
+  type SerializeTupleVariant = Impossible<S::Ok, S::Error>;
+  type SerializeMap = S::SerializeMap;
+  type SerializeStruct = TaggedStruct<S::SerializeStruct>;
+  type SerializeStructVariant = Impossible<S::Ok, S::Error>;
+
+  fn serialize_bool(self, _v: bool) -> Result<S::Ok, S::Error> { Err(Self::unsupported("a bool")) }
+  fn serialize_i8(self, _v: i8) -> Result<S::Ok, S::Error> { Err(Self::unsupported("an i8")) }
+  fn serialize_i16(self, _v: i16) -> Result<S::Ok, S::Error> { Err(Self::unsupported("an i16")) }
+  fn serialize_i32(self, _v: i32) -> Result<S::Ok, S::Error> { Err(Self::unsupported("an i32")) }
+  fn serialize_i64(self, _v: i64) -> Result<S::Ok, S::Error> { Err(Self::unsupported("an i64")) }
+  fn serialize_i128(self, _v: i128) -> Result<S::Ok, S::Error> { Err(Self::unsupported("an i128")) }
+  fn serialize_u8(self, _v: u8) -> Result<S::Ok, S::Error> { Err(Self::unsupported("a u8")) }
+  fn serialize_u16(self, _v: u16) -> Result<S::Ok, S::Error> { Err(Self::unsupported("a u16")) }
+  fn serialize_u32(self, _v: u32) -> Result<S::Ok, S::Error> { Err(Self::unsupported("a u32")) }
+  fn serialize_u64(self, _v: u64) -> Result<S::Ok, S::Error> { Err(Self::unsupported("a u64")) }
+  fn serialize_u128(self, _v: u128) -> Result<S::Ok, S::Error> { Err(Self::unsupported("a u128")) }
+  fn serialize_f32(self, _v: f32) -> Result<S::Ok, S::Error> { Err(Self::unsupported("an f32")) }
+  fn serialize_f64(self, _v: f64) -> Result<S::Ok, S::Error> { Err(Self::unsupported("an f64")) }
+  fn serialize_char(self, _v: char) -> Result<S::Ok, S::Error> { Err(Self::unsupported("a char")) }
+  fn serialize_str(self, _v: &str) -> Result<S::Ok, S::Error> { Err(Self::unsupported("a string")) }
+  fn serialize_bytes(self, _v: &[u8]) -> Result<S::Ok, S::Error> { Err(Self::unsupported("bytes")) }
+  fn serialize_none(self) -> Result<S::Ok, S::Error> { Err(Self::unsupported("an option")) }
+  fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<S::Ok, S::Error> {
+    Err(Self::unsupported("an option"))
+  }
+
+  #[inline]
+  fn serialize_unit(self) -> Result<S::Ok, S::Error> {
+    let mut map = self.delegate.serialize_map(Some(1))?;
+    map.serialize_entry(self.tag, self.id)?;
+    map.end()
+  }
+  #[inline]
+  fn serialize_unit_struct(self, name: &'static str) -> Result<S::Ok, S::Error> {
+    let mut state = self.delegate.serialize_struct(name, 1)?;
+    state.serialize_field(self.tag, self.id)?;
+    state.end()
+  }
+  #[inline]
+  fn serialize_unit_variant(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+  ) -> Result<S::Ok, S::Error> {
+    let mut map = self.delegate.serialize_map(Some(2))?;
+    map.serialize_entry(self.tag, self.id)?;
+    map.serialize_entry(variant, &())?;
+    map.end()
+  }
+
+  #[inline]
+  fn serialize_newtype_struct<T: ?Sized + Serialize>(self, _name: &'static str, value: &T) -> Result<S::Ok, S::Error> {
+    value.serialize(self)
+  }
+  #[inline]
+  fn serialize_newtype_variant<T: ?Sized + Serialize>(
+    self,
+    _name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+    value: &T,
+  ) -> Result<S::Ok, S::Error> {
+    let mut map = self.delegate.serialize_map(Some(2))?;
+    map.serialize_entry(self.tag, self.id)?;
+    map.serialize_entry(variant, value)?;
+    map.end()
+  }
+
+  fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+    Err(Self::unsupported("a sequence"))
+  }
+  fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, S::Error> {
+    Err(Self::unsupported("a tuple"))
+  }
+  fn serialize_tuple_struct(self, name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, S::Error> {
+    Err(Self::unsupported(format_args!("tuple struct `{}`", name)))
+  }
+  fn serialize_tuple_variant(
+    self,
+    name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeTupleVariant, S::Error> {
+    Err(Self::unsupported(format_args!("tuple variant `{}::{}`", name, variant)))
+  }
+
+  #[inline]
+  fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+    let mut map = self.delegate.serialize_map(len.map(|len| len + 1))?;
+    map.serialize_entry(self.tag, self.id)?;
+    Ok(map)
+  }
+  #[inline]
+  fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct, S::Error> {
+    let mut state = self.delegate.serialize_struct(name, len + 1)?;
+    state.serialize_field(self.tag, self.id)?;
+    Ok(TaggedStruct { tag: self.tag, name, delegate: state })
+  }
+  fn serialize_struct_variant(
+    self,
+    name: &'static str,
+    _variant_index: u32,
+    variant: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeStructVariant, S::Error> {
+    Err(Self::unsupported(format_args!("struct variant `{}::{}`", name, variant)))
+  }
+
+  #[inline]
+  fn is_human_readable(&self) -> bool { self.delegate.is_human_readable() }
+}
+
+
+/// [`SerializeStruct`] that fails when serializing a field with the same name as `tag`, before serializing with
+/// `delegate`.
+struct TaggedStruct<S> {
+  tag: &'static str,
+  name: &'static str,
+  delegate: S,
+}
+
+impl<S: SerializeStruct> SerializeStruct for TaggedStruct<S> {
+  type Ok = S::Ok;
+  type Error = S::Error;
+
+  #[inline]
+  fn serialize_field<T: ?Sized + Serialize>(&mut self, key: &'static str, value: &T) -> Result<(), S::Error> {
+    if key == self.tag {
+      return Err(ser::Error::custom(format_args!(
+        "cannot serialize struct `{}` as internally tagged: it has a field named like the tag `{}`", self.name, self.tag
+      )));
+    }
+    self.delegate.serialize_field(key, value)
+  }
+  #[inline]
+  fn skip_field(&mut self, key: &'static str) -> Result<(), S::Error> { self.delegate.skip_field(key) }
+  #[inline]
+  fn end(self) -> Result<S::Ok, S::Error> { self.delegate.end() }
+}
+
+
+/// Serialize `trait_object` as a single string `id:value`, where `id` is the unique identifier for the concrete type of
+/// `trait_object`, and `value` is `trait_object` encoded by `codec`. Use this for trait objects in map keys of formats
+/// that only support string keys, such as JSON. See the [key](crate::key) module for more info.
+pub struct SerializeTraitObjectKey<'o, I, O: ?Sized, C> {
+  pub id: I,
+  pub trait_object: &'o O,
+  pub codec: C,
+}
+
+impl<I, O, C> Serialize for SerializeTraitObjectKey<'_, I, O, C> where
+  I: Display,
+  O: ?Sized + erased_serde::Serialize,
+  C: KeyCodec,
+{
+  #[inline]
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+    S: Serializer
+  {
+    let id = self.id.to_string();
+    if id.contains(KEY_SEPARATOR) {
+      return Err(ser::Error::custom(format_args!("id '{}' contains key separator '{}'", id, KEY_SEPARATOR)));
+    }
+    let value = self.codec.encode(&Wrap(self.trait_object)).map_err(ser::Error::custom)?;
+    serializer.collect_str(&format_args!("{}{}{}", id, KEY_SEPARATOR, value))
+  }
+}
+
+
+/// Wrapper so we can implement [`Serialize`] for `Wrap(O)`.
+#[repr(transparent)]
+struct Wrap<'a, O: ?Sized>(&'a O);
+
+impl<O> Serialize for Wrap<'_, O> where
+  O: ?Sized + erased_serde::Serialize,
+{
+  #[inline]
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    erased_serde::serialize(self.0, serializer)
+  }
+}
+
 /// Checks whether `T` implements [`erased_serde::Serialize`].
 pub const fn require_erased_serialize_impl<T: ?Sized + erased_serde::Serialize>() {}
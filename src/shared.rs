@@ -0,0 +1,189 @@
+//! Opt-in (de)serialization of [`Arc`] trait objects that preserves sharing. The first time an [`Arc`] is serialized
+//! with a [`SerializeContext`], it is assigned a reference ID and serialized as a `(reference, Some(trait_object))`
+//! tuple. Further serializations of the same [`Arc`] only serialize a `(reference, None)` back-reference. Deserializing
+//! with a [`DeserializeContext`] restores back-references as clones of the same [`Arc`]. Deserializing a trait object
+//! for a reference that was already deserialized, or a back-reference to a reference that was not deserialized yet, is
+//! an error.
+//!
+//! Both contexts must be used for the entire (de)serialization of a data structure, in the same order.
+
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+use serde::de::{self, Deserializer, DeserializeSeed, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeTuple, Serializer};
+use serde::Deserialize;
+
+use crate::de::DeserializeTraitObjectArc;
+use crate::Registry;
+
+/// Context for serializing shared [`Arc`] trait objects, assigning reference IDs to [`Arc`]s by their address. Keep
+/// all serialized [`Arc`]s alive while using this context, as a dropped [`Arc`]'s address can be reused by another.
+#[derive(Default, Debug)]
+pub struct SerializeContext {
+  references: RefCell<HashMap<*const (), u64>>,
+}
+
+impl SerializeContext {
+  /// Creates a new context without any references.
+  #[inline]
+  pub fn new() -> Self { Self::default() }
+
+  /// Gets the reference ID for `trait_object`, and whether it was newly assigned.
+  fn reference<O: ?Sized>(&self, trait_object: &Arc<O>) -> (u64, bool) {
+    let mut references = self.references.borrow_mut();
+    let next = references.len() as u64;
+    let address = Arc::as_ptr(trait_object) as *const ();
+    let reference = *references.entry(address).or_insert(next);
+    (reference, reference == next)
+  }
+}
+
+/// Serialize `trait_object` as a `(reference, Some(trait_object))` tuple the first time it is serialized with
+/// `context`, and as a `(reference, None)` back-reference afterwards.
+pub struct SerializeShared<'a, O: ?Sized> {
+  pub context: &'a SerializeContext,
+  pub trait_object: &'a Arc<O>,
+}
+impl<O> Serialize for SerializeShared<'_, O> where
+  O: ?Sized + Serialize,
+{
+  #[inline]
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+    S: Serializer
+  {
+    let (reference, first) = self.context.reference(self.trait_object);
+    let mut tuple = serializer.serialize_tuple(2)?;
+    tuple.serialize_element(&reference)?;
+    tuple.serialize_element(&first.then_some(self.trait_object.as_ref()))?;
+    tuple.end()
+  }
+}
+
+
+/// Context for deserializing shared [`Arc`] trait objects, storing deserialized [`Arc`]s by their reference ID.
+pub struct DeserializeContext<O: ?Sized> {
+  shared: RefCell<HashMap<u64, Arc<O>>>,
+}
+
+impl<O: ?Sized> DeserializeContext<O> {
+  /// Creates a new context without any shared trait objects.
+  #[inline]
+  pub fn new() -> Self { Self { shared: RefCell::new(HashMap::new()) } }
+}
+impl<O: ?Sized> Default for DeserializeContext<O> {
+  #[inline]
+  fn default() -> Self { Self::new() }
+}
+impl<O: ?Sized> Debug for DeserializeContext<O> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_struct("DeserializeContext").field("shared", &self.shared.borrow().len()).finish()
+  }
+}
+
+/// Deserialize [`Arc<<R as Registry>::TraitObject>`](Self::Value) from a `(reference, Some(trait_object))` tuple, or
+/// from a `(reference, None)` back-reference to an [`Arc`] previously deserialized with `context`, using the registry
+/// to get deserialize functions for concrete types of the trait object. Implements [`DeserializeSeed`].
+pub struct DeserializeShared<'a, R: Registry> {
+  pub registry: &'a R,
+  pub context: &'a DeserializeContext<R::TraitObject>,
+}
+
+impl<'de, R: Registry> DeserializeSeed<'de> for DeserializeShared<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Arc<R::TraitObject>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_tuple(2, self)
+  }
+}
+
+impl<'de, R: Registry> Visitor<'de> for DeserializeShared<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Arc<R::TraitObject>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "a reference-trait object pair for shared `Arc<dyn {}>`", self.registry.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let Some(reference) = seq.next_element::<u64>()? else {
+      return Err(de::Error::invalid_length(0, &self));
+    };
+    let Some(trait_object) = seq.next_element_seed(OptionalTraitObjectArc(self.registry))? else {
+      return Err(de::Error::invalid_length(1, &self));
+    };
+    if let Some(trait_object) = trait_object {
+      match self.context.shared.borrow_mut().entry(reference) {
+        Entry::Occupied(_) => Err(de::Error::custom(format_args!(
+          "shared `Arc<dyn {}>` with reference '{}' was already deserialized before, expected a back-reference",
+          self.registry.get_trait_object_name(),
+          reference
+        ))),
+        Entry::Vacant(entry) => Ok(entry.insert(trait_object).clone()),
+      }
+    } else {
+      self.context.shared.borrow().get(&reference).cloned().ok_or_else(|| de::Error::custom(format_args!(
+        "back-reference '{}' to shared `Arc<dyn {}>` was not deserialized before",
+        reference,
+        self.registry.get_trait_object_name()
+      )))
+    }
+  }
+}
+
+impl<R: Registry> Copy for DeserializeShared<'_, R> {}
+impl<R: Registry> Clone for DeserializeShared<'_, R> {
+  #[inline]
+  fn clone(&self) -> Self { *self }
+}
+
+
+/// Deserialize `Option<Arc<<R as Registry>::TraitObject>>`.
+#[repr(transparent)]
+struct OptionalTraitObjectArc<'r, R>(&'r R);
+
+impl<'de, R: Registry> DeserializeSeed<'de> for OptionalTraitObjectArc<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Option<Arc<R::TraitObject>>;
+
+  #[inline]
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    deserializer.deserialize_option(self)
+  }
+}
+
+impl<'de, R: Registry> Visitor<'de> for OptionalTraitObjectArc<'_, R> where
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  type Value = Option<Arc<R::TraitObject>>;
+
+  #[inline]
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+    write!(formatter, "an optional id-value pair for `Arc<dyn {}>`", self.0.get_trait_object_name())
+  }
+
+  #[inline]
+  fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+    Ok(None)
+  }
+
+  #[inline]
+  fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+    Ok(None)
+  }
+
+  #[inline]
+  fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    DeserializeTraitObjectArc(self.0).deserialize(deserializer).map(Some)
+  }
+}
@@ -0,0 +1,297 @@
+//! Functions for (de)serializing fields containing trait objects with a specific registry, for use in modules for
+//! `#[serde(with = "module")]` attributes. This is required when `Deserialize` is not implemented for `Box<dyn Trait>`,
+//! for example because multiple registries exist for the same trait.
+//!
+//! Use the [`serde_with_registry`](crate::serde_with_registry) macro to generate such a module for a registry:
+//!
+//! ```
+//! # use std::collections::HashMap;
+//! # use std::fmt::Debug;
+//! # use std::sync::LazyLock;
+//! # use serde::{Deserialize, Serialize};
+//! # use serde_flexitos::{MapRegistry, Registry};
+//! pub trait Example: erased_serde::Serialize + Debug {
+//!   fn id(&self) -> &'static str;
+//! }
+//!
+//! #[derive(Serialize, Deserialize, Debug)]
+//! struct Foo(String);
+//! impl Example for Foo {
+//!   fn id(&self) -> &'static str { "Foo" }
+//! }
+//! impl From<Foo> for Box<dyn Example> {
+//!   fn from(value: Foo) -> Self { Box::new(value) }
+//! }
+//!
+//! static REGISTRY: LazyLock<MapRegistry<dyn Example>> = LazyLock::new(|| {
+//!   let mut registry = MapRegistry::<dyn Example>::new("Example");
+//!   registry.register_type::<Foo>("Foo");
+//!   registry
+//! });
+//!
+//! serde_flexitos::serde_with_registry!(mod example for dyn Example, registry: &*REGISTRY, id: Example::id);
+//!
+//! #[derive(Serialize, Deserialize, Debug)]
+//! struct Container {
+//!   #[serde(with = "example")]
+//!   single: Box<dyn Example>,
+//!   #[serde(with = "example::vec")]
+//!   multiple: Vec<Box<dyn Example>>,
+//!   #[serde(with = "example::option", default)]
+//!   optional: Option<Box<dyn Example>>,
+//!   #[serde(with = "example::map")]
+//!   map: HashMap<String, Box<dyn Example>>,
+//! }
+//!
+//! # fn main() -> Result<(), serde_json::Error> {
+//! let json = r#"{"single":{"Foo":"A"},"multiple":[{"Foo":"B"}],"map":{"c":{"Foo":"C"}}}"#;
+//! let container: Container = serde_json::from_str(json)?;
+//! let expected = r#"{"single":{"Foo":"A"},"multiple":[{"Foo":"B"}],"optional":null,"map":{"c":{"Foo":"C"}}}"#;
+//! assert_eq!(serde_json::to_string(&container)?, expected);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use serde::de::{DeserializeSeed, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use serde::Deserialize;
+
+use crate::de::{DeserializeMapWith, DeserializeOptionWith, DeserializeTraitObject, DeserializeVecWithTraitObject};
+use crate::ser::SerializeTraitObject;
+use crate::Registry;
+
+/// Serialize `trait_object` with `serializer`, using `id` to get the unique identifier for its concrete type.
+#[inline]
+pub fn serialize<S, O, I>(trait_object: &O, id: impl Fn(&O) -> I, serializer: S) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  O: ?Sized + erased_serde::Serialize,
+  I: Serialize,
+{
+  SerializeTraitObject { id: id(trait_object), trait_object }.serialize(serializer)
+}
+
+/// Serialize `trait_objects` as a sequence with `serializer`, using `id` to get the unique identifiers for their
+/// concrete types.
+#[inline]
+pub fn serialize_vec<S, O, I>(
+  trait_objects: &[Box<O>],
+  id: impl Fn(&O) -> I,
+  serializer: S,
+) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  O: ?Sized + erased_serde::Serialize,
+  I: Serialize,
+{
+  serializer.collect_seq(trait_objects.iter().map(|trait_object| {
+    SerializeTraitObject { id: id(trait_object), trait_object: trait_object.as_ref() }
+  }))
+}
+
+/// Serialize `trait_object` as an option with `serializer`, using `id` to get the unique identifier for its concrete
+/// type.
+#[inline]
+pub fn serialize_option<S, O, I>(
+  trait_object: &Option<Box<O>>,
+  id: impl Fn(&O) -> I,
+  serializer: S,
+) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  O: ?Sized + erased_serde::Serialize,
+  I: Serialize,
+{
+  match trait_object {
+    Some(trait_object) => {
+      serializer.serialize_some(&SerializeTraitObject { id: id(trait_object), trait_object: trait_object.as_ref() })
+    }
+    None => serializer.serialize_none(),
+  }
+}
+
+/// Serialize `map` with trait object values with `serializer`, using `id` to get the unique identifiers for their
+/// concrete types.
+#[inline]
+pub fn serialize_map<S, K, O, I>(
+  map: &HashMap<K, Box<O>>,
+  id: impl Fn(&O) -> I,
+  serializer: S,
+) -> Result<S::Ok, S::Error> where
+  S: Serializer,
+  K: Serialize,
+  O: ?Sized + erased_serde::Serialize,
+  I: Serialize,
+{
+  serializer.collect_map(map.iter().map(|(key, trait_object)| {
+    (key, SerializeTraitObject { id: id(trait_object), trait_object: trait_object.as_ref() })
+  }))
+}
+
+/// Deserialize a trait object with `deserializer`, using `registry` to get deserialize functions for concrete types.
+///
+/// # Errors
+///
+/// Returns an error when [get_deserialize_fn](Registry::get_deserialize_fn) returns an error for the deserialized ID,
+/// or when deserialization fails.
+#[inline]
+pub fn deserialize<'de, D, R>(registry: &R, deserializer: D) -> Result<Box<R::TraitObject>, D::Error> where
+  D: Deserializer<'de>,
+  R: Registry,
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  DeserializeTraitObject(registry).deserialize(deserializer)
+}
+
+/// Deserialize a sequence of trait objects with `deserializer`, using `registry` to get deserialize functions for
+/// concrete types.
+///
+/// # Errors
+///
+/// Returns an error when [get_deserialize_fn](Registry::get_deserialize_fn) returns an error for a deserialized ID, or
+/// when deserialization fails.
+#[inline]
+pub fn deserialize_vec<'de, D, R>(registry: &R, deserializer: D) -> Result<Vec<Box<R::TraitObject>>, D::Error> where
+  D: Deserializer<'de>,
+  R: Registry,
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  DeserializeVecWithTraitObject(registry).deserialize(deserializer)
+}
+
+/// Deserialize an optional trait object with `deserializer`, using `registry` to get deserialize functions for
+/// concrete types.
+///
+/// # Errors
+///
+/// Returns an error when [get_deserialize_fn](Registry::get_deserialize_fn) returns an error for the deserialized ID,
+/// or when deserialization fails.
+#[inline]
+pub fn deserialize_option<'de, D, R>(
+  registry: &R,
+  deserializer: D,
+) -> Result<Option<Box<R::TraitObject>>, D::Error> where
+  D: Deserializer<'de>,
+  R: Registry,
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  DeserializeOptionWith(DeserializeTraitObject(registry)).deserialize(deserializer)
+}
+
+/// Deserialize a map with trait object values with `deserializer`, using `registry` to get deserialize functions for
+/// concrete types.
+///
+/// # Errors
+///
+/// Returns an error when [get_deserialize_fn](Registry::get_deserialize_fn) returns an error for a deserialized ID, or
+/// when deserialization fails.
+#[inline]
+pub fn deserialize_map<'de, D, K, R>(
+  registry: &R,
+  deserializer: D,
+) -> Result<HashMap<K, Box<R::TraitObject>>, D::Error> where
+  D: Deserializer<'de>,
+  K: Deserialize<'de> + Eq + Hash,
+  R: Registry,
+  R::Identifier: Deserialize<'de> + Debug,
+{
+  DeserializeMapWith::trait_object_value(registry).deserialize(deserializer)
+}
+
+
+/// Generates a module for `#[serde(with = "module")]` attributes on fields of type `Box<dyn Trait>`, with submodules
+/// `vec`, `option`, and `map` for fields of type `Vec<Box<dyn Trait>>`, `Option<Box<dyn Trait>>`, and
+/// `HashMap<K, Box<dyn Trait>>`. The generated module (de)serializes using the functions in the [with](crate::with)
+/// module, with `registry` (an expression of type `&impl Registry`), and with `id` (an expression of type
+/// `impl Fn(&dyn Trait) -> impl Serialize`) to get the unique identifier of a trait object. Items of the parent module
+/// are in scope for these expressions.
+///
+/// ```ignore
+/// serde_with_registry!(pub mod example for dyn Example, registry: &*REGISTRY, id: Example::id);
+/// ```
+///
+/// Fields of type `Option<Box<dyn Trait>>` should also be annotated with `#[serde(default)]` to allow a missing field.
+#[macro_export]
+macro_rules! serde_with_registry {
+  ($vis:vis mod $module:ident for $trait_object:ty, registry: $registry:expr, id: $id:expr $(,)?) => {
+    $vis mod $module {
+      #[allow(unused_imports)]
+      use super::*;
+
+      #[inline]
+      pub fn serialize<S>(trait_object: &Box<$trait_object>, serializer: S) -> Result<S::Ok, S::Error> where
+        S: $crate::__private::serde::Serializer,
+      {
+        $crate::with::serialize(trait_object.as_ref(), $id, serializer)
+      }
+      #[inline]
+      pub fn deserialize<'de, D>(deserializer: D) -> Result<Box<$trait_object>, D::Error> where
+        D: $crate::__private::serde::Deserializer<'de>,
+      {
+        $crate::with::deserialize($registry, deserializer)
+      }
+
+      pub mod vec {
+        #[allow(unused_imports)]
+        use super::*;
+
+        #[inline]
+        pub fn serialize<S>(trait_objects: &[Box<$trait_object>], serializer: S) -> Result<S::Ok, S::Error> where
+          S: $crate::__private::serde::Serializer,
+        {
+          $crate::with::serialize_vec(trait_objects, $id, serializer)
+        }
+        #[inline]
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Box<$trait_object>>, D::Error> where
+          D: $crate::__private::serde::Deserializer<'de>,
+        {
+          $crate::with::deserialize_vec($registry, deserializer)
+        }
+      }
+
+      pub mod option {
+        #[allow(unused_imports)]
+        use super::*;
+
+        #[inline]
+        pub fn serialize<S>(trait_object: &Option<Box<$trait_object>>, serializer: S) -> Result<S::Ok, S::Error> where
+          S: $crate::__private::serde::Serializer,
+        {
+          $crate::with::serialize_option(trait_object, $id, serializer)
+        }
+        #[inline]
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Box<$trait_object>>, D::Error> where
+          D: $crate::__private::serde::Deserializer<'de>,
+        {
+          $crate::with::deserialize_option($registry, deserializer)
+        }
+      }
+
+      pub mod map {
+        #[allow(unused_imports)]
+        use super::*;
+
+        #[inline]
+        pub fn serialize<S, K>(
+          map: &std::collections::HashMap<K, Box<$trait_object>>,
+          serializer: S,
+        ) -> Result<S::Ok, S::Error> where
+          S: $crate::__private::serde::Serializer,
+          K: $crate::__private::serde::Serialize,
+        {
+          $crate::with::serialize_map(map, $id, serializer)
+        }
+        #[inline]
+        pub fn deserialize<'de, D, K>(
+          deserializer: D,
+        ) -> Result<std::collections::HashMap<K, Box<$trait_object>>, D::Error> where
+          D: $crate::__private::serde::Deserializer<'de>,
+          K: $crate::__private::serde::Deserialize<'de> + Eq + std::hash::Hash,
+        {
+          $crate::with::deserialize_map($registry, deserializer)
+        }
+      }
+    }
+  };
+}
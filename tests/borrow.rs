@@ -0,0 +1,94 @@
+//! Tests for deserializing trait objects that borrow from the input.
+
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+use serde::de::DeserializeSeed;
+use serde::Deserialize;
+
+use serde_flexitos::borrow::{BorrowMapRegistry, BorrowRegistry, DeserializeBorrowTraitObject, TraitObjectFamily};
+use serde_flexitos::de::DeserializeSeqWith;
+
+trait Event: Debug {
+  fn message(&self) -> &str;
+}
+
+struct EventFamily;
+impl TraitObjectFamily for EventFamily {
+  type TraitObject<'de> = dyn Event + 'de;
+}
+
+#[derive(Deserialize, Debug)]
+struct Warning<'a>(&'a str);
+impl Event for Warning<'_> {
+  fn message(&self) -> &str { self.0 }
+}
+
+#[derive(Deserialize, Debug)]
+struct Note<'a>(#[serde(borrow)] Cow<'a, str>);
+impl Event for Note<'_> {
+  fn message(&self) -> &str { &self.0 }
+}
+
+fn registry() -> BorrowMapRegistry<EventFamily> {
+  let mut registry = BorrowMapRegistry::<EventFamily>::new("Event");
+  registry.register("Warning", |d| Ok(Box::new(erased_serde::deserialize::<Warning>(d)?)));
+  registry.register("Note", |d| Ok(Box::new(erased_serde::deserialize::<Note>(d)?)));
+  registry
+}
+
+/// Returns whether `inner` points into `outer`.
+fn borrows_from(inner: &str, outer: &str) -> bool {
+  outer.as_bytes().as_ptr_range().contains(&inner.as_ptr())
+}
+
+
+#[test]
+fn borrow_from_input() {
+  let registry = registry();
+  let json = r#"{"Warning":"disk almost full"}"#.to_string();
+  let event = registry.deserialize_trait_object(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+  assert_eq!(event.message(), "disk almost full");
+  assert!(borrows_from(event.message(), &json));
+}
+
+#[test]
+fn borrow_sequence() {
+  let registry = registry();
+  let json = r#"[{"Warning":"a"},{"Note":"b"},{"Note":"escaped \"c\""}]"#.to_string();
+  let seed = DeserializeSeqWith::new(DeserializeBorrowTraitObject(&registry));
+  let events: Vec<Box<dyn Event>> = seed.deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+  let messages: Vec<_> = events.iter().map(|event| event.message()).collect();
+  assert_eq!(messages, vec!["a", "b", "escaped \"c\""]);
+  assert!(borrows_from(messages[0], &json));
+  assert!(borrows_from(messages[1], &json));
+  // Strings with escape sequences cannot be borrowed, so `Cow` allocates instead.
+  assert!(!borrows_from(messages[2], &json));
+}
+
+#[test]
+fn borrow_escaped_str_fails() {
+  let registry = registry();
+  let json = r#"{"Warning":"escaped \"path\""}"#;
+  assert!(registry.deserialize_trait_object(&mut serde_json::Deserializer::from_str(json)).is_err());
+}
+
+#[test]
+fn borrow_unknown_id() {
+  let registry = registry();
+  let json = r#"{"Error":"a"}"#;
+  let error = registry.deserialize_trait_object(&mut serde_json::Deserializer::from_str(json)).unwrap_err();
+  assert!(error.to_string().contains("no deserialize function was registered for id '\"Error\"'"), "{}", error);
+}
+
+#[test]
+fn borrow_multiple_registrations() {
+  let mut registry = registry();
+  let line = line!() + 1;
+  registry.register("Warning", |d| Ok(Box::new(erased_serde::deserialize::<Note>(d)?)));
+  let json = r#"{"Warning":"a"}"#;
+  let error = registry.deserialize_trait_object(&mut serde_json::Deserializer::from_str(json)).unwrap_err();
+  assert!(error.to_string().contains("multiple deserialize functions were registered"), "{}", error);
+  assert!(error.to_string().contains(&format!("unknown type at {}:{}", file!(), line)), "{}", error);
+  assert_eq!(registry.get_ids().copied().collect::<Vec<_>>(), vec!["Note", "Warning"]);
+}
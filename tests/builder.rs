@@ -0,0 +1,128 @@
+//! Tests for validating registrations when building registries.
+
+mod common;
+
+use std::any::TypeId;
+use std::fmt::{self, Debug, Display, Formatter};
+
+use bincode::Options;
+use serde_flexitos::{compact_id, serialize_trait_object_variant, Registry};
+use serde_flexitos::builder::{CompactIdCollision, RegistryBuilder};
+use common::{Circle, Color, Label, Shape};
+
+
+#[test]
+fn build_valid() {
+  let mut builder = RegistryBuilder::<dyn Shape>::new("Shape");
+  builder.register_type::<Circle>(Circle::ID);
+  builder.register_type::<Label>(Label::ID);
+  // Registering the same ID for a type ID again is not a conflict.
+  builder.register_type_id(TypeId::of::<Label>(), Label::ID);
+  let registry = builder.build().unwrap();
+
+  assert_eq!(registry.get_id_by_type_id(TypeId::of::<Circle>()).ok(), Some(&Circle::ID));
+  assert_eq!(registry.get_id_by_type_id(TypeId::of::<Label>()).ok(), Some(&Label::ID));
+  let mut deserializer = serde_json::Deserializer::from_str(r##"{"Label":{"text":"A","color":"#0080ff"}}"##);
+  let label = registry.deserialize_trait_object(&mut deserializer).unwrap();
+  assert_eq!(label.as_ref(), &Label { text: "A".to_string(), color: Color([0, 128, 255]) } as &dyn Shape);
+}
+
+#[test]
+fn build_variants() {
+  let mut builder = RegistryBuilder::<dyn Shape>::new("Shape");
+  builder.register_type::<Circle>(Circle::ID);
+  builder.register_type::<Label>(Label::ID);
+  builder.set_variants(&[Label::ID, Circle::ID]);
+  let registry = builder.build().unwrap();
+  assert_eq!(registry.get_variants(), &[Label::ID, Circle::ID]);
+
+  let circle = Circle { radius: 2 };
+  let mut bytes = Vec::new();
+  let mut serializer = bincode::Serializer::new(&mut bytes, bincode::options());
+  serialize_trait_object_variant(&mut serializer, &registry, Circle::ID, &circle).unwrap();
+  assert_eq!(bytes, bincode::options().serialize(&(1u32, &circle)).unwrap());
+  let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+  let deserialized = registry.deserialize_trait_object_variant(&mut deserializer).unwrap();
+  assert_eq!(deserialized.as_ref(), &circle as &dyn Shape);
+}
+
+#[test]
+fn build_duplicate_ids() {
+  let mut builder = RegistryBuilder::<dyn Shape>::new("Shape");
+  let line = line!() + 1;
+  builder.register_type::<Circle>(Circle::ID);
+  builder.register_type::<Label>(Circle::ID);
+  builder.register(Circle::ID, |deserializer| Ok(Box::new(erased_serde::deserialize::<Circle>(deserializer)?)));
+  let error = builder.build().err().unwrap();
+
+  assert_eq!(error.duplicates.len(), 1);
+  let duplicate = &error.duplicates[0];
+  assert_eq!(duplicate.id, Circle::ID);
+  assert_eq!(duplicate.registrations.len(), 3);
+  assert!(duplicate.registrations[0].type_name.unwrap().ends_with("Circle"));
+  assert!(duplicate.registrations[1].type_name.unwrap().ends_with("Label"));
+  assert_eq!(duplicate.registrations[2].type_name, None);
+  let lines: Vec<_> = duplicate.registrations.iter().map(|registration| registration.location.line()).collect();
+  assert_eq!(lines, [line, line + 1, line + 2]);
+  assert!(error.compact_id_collisions.is_empty());
+  assert!(error.type_id_conflicts.is_empty());
+  let message = error.to_string();
+  assert!(message.contains("`dyn Shape`"), "{}", message);
+  assert!(message.contains("multiple deserialize functions were registered for id '\"Circle\"'"), "{}", message);
+  assert!(message.contains(&duplicate.registrations[2].to_string()), "{}", message);
+}
+
+#[test]
+fn build_without_display() {
+  // IDs only need to implement `Display` to check compact IDs.
+  let mut builder = RegistryBuilder::<dyn Shape, (&'static str, u32)>::new("Shape");
+  builder.register_type::<Circle>((Circle::ID, 1));
+  let registry = builder.build().unwrap();
+  assert_eq!(registry.get_id_by_type_id(TypeId::of::<Circle>()).ok(), Some(&(Circle::ID, 1)));
+}
+
+/// ID with a version that is not part of its display form, so that different IDs have the same compact ID.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+struct VersionedId(&'static str, u32);
+impl Display for VersionedId {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { f.write_str(self.0) }
+}
+
+#[test]
+fn build_compact_id_collisions() {
+  let builder = || {
+    let mut builder = RegistryBuilder::<dyn Shape, VersionedId>::new("Shape");
+    builder.register_type::<Circle>(VersionedId(Circle::ID, 1));
+    builder.register_type::<Label>(VersionedId(Circle::ID, 2));
+    builder.register_type::<Label>(VersionedId(Label::ID, 1));
+    builder
+  };
+  // Only checked when building with compact IDs.
+  assert!(builder().build().err().unwrap().compact_id_collisions.is_empty());
+  let error = builder().build_with_compact_ids().err().unwrap();
+
+  assert!(error.duplicates.is_empty());
+  assert_eq!(error.compact_id_collisions, vec![CompactIdCollision {
+    compact_id: compact_id(Circle::ID),
+    ids: vec![VersionedId(Circle::ID, 1), VersionedId(Circle::ID, 2)],
+  }]);
+  assert!(error.to_string().contains("have the same compact id"), "{}", error);
+}
+
+#[test]
+fn build_type_id_conflicts() {
+  let mut builder = RegistryBuilder::<dyn Shape>::new("Shape");
+  builder.register_type::<Circle>(Circle::ID);
+  builder.register_type::<Circle>("Round");
+  builder.register_type::<Label>(Label::ID);
+  let error = builder.build().err().unwrap();
+
+  assert!(error.duplicates.is_empty());
+  assert_eq!(error.type_id_conflicts.len(), 1);
+  assert_eq!(error.type_id_conflicts[0].type_id, TypeId::of::<Circle>());
+  let registrations = &error.type_id_conflicts[0].registrations;
+  let ids: Vec<_> = registrations.iter().map(|(id, _)| *id).collect();
+  assert_eq!(ids, [Circle::ID, "Round"]);
+  assert!(registrations.iter().all(|(_, info)| info.type_name.unwrap().ends_with("Circle")));
+  assert!(error.to_string().contains("different ids were registered for type id"), "{}", error);
+}
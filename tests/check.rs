@@ -0,0 +1,48 @@
+//! Tests for checking serialize IDs against registered IDs.
+
+mod common;
+
+use std::any::TypeId;
+
+use serde_flexitos::{GetError, MapRegistry, Registry};
+use serde_flexitos::check::{check_ids, IdMismatch};
+use common::{registry, shapes, Circle, Label, Shape};
+
+fn check(registry: &MapRegistry<dyn Shape>, samples: &[Box<dyn Shape>]) -> Result<(), Vec<IdMismatch<&'static str>>> {
+  check_ids(registry, samples.iter().map(|s| s.as_ref()), |s| s.as_any().type_id(), |s| s.id())
+}
+
+
+#[test]
+fn check_ids_consistent() {
+  assert_eq!(check(&registry(), &shapes()), Ok(()));
+}
+
+#[test]
+fn check_ids_mismatch() {
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  registry.register_type::<Circle>(Circle::ID);
+  registry.register_type::<Label>("Text");
+  let mismatches = check(&registry, &shapes()).unwrap_err();
+  assert_eq!(mismatches.len(), 1);
+  assert_eq!(mismatches[0].index, 1);
+  assert_eq!(mismatches[0].type_id, TypeId::of::<Label>());
+  assert_eq!(mismatches[0].serialize_id, Label::ID);
+  assert_eq!(mismatches[0].registered_id, Ok("Text"));
+  assert_eq!(
+    mismatches[0].to_string(),
+    "sample 1 is serialized with id '\"Label\"', but its type is registered with id '\"Text\"'"
+  );
+}
+
+#[test]
+fn check_ids_not_registered_or_ambiguous() {
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  registry.register_type::<Circle>(Circle::ID);
+  registry.register_type::<Circle>("Round");
+  let mismatches = check(&registry, &shapes()).unwrap_err();
+  assert_eq!(mismatches.len(), 2);
+  assert!(matches!(mismatches[0].registered_id, Err(GetError::MultipleRegistrations { .. })));
+  assert!(matches!(mismatches[1].registered_id, Err(GetError::NotRegistered { .. })));
+  assert!(mismatches[1].to_string().contains("no id was registered for its type"), "{}", mismatches[1]);
+}
@@ -0,0 +1,156 @@
+//! Tests for deserializing collections of trait objects.
+
+mod common;
+
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use serde::de::DeserializeSeed;
+use serde::{Deserializer, Serialize, Serializer};
+
+use serde_flexitos::{serialize_trait_object, MapRegistry};
+use serde_flexitos::de::{
+  DeserializeArrayWith, DeserializeMapWith, DeserializeOptionWith, DeserializeSeqWith, DeserializeTraitObject,
+  DeserializeTupleWith, DeserializeVecWithTraitObjectCollectErrors, ElementError,
+};
+use common::{registry, shapes, Circle, Color, Label, Shape};
+
+/// Seed that counts the trait objects it deserializes, which is `Clone` but not `Copy`.
+#[derive(Clone)]
+struct CountingSeed<'r> {
+  registry: &'r MapRegistry<dyn Shape>,
+  count: Rc<Cell<usize>>,
+}
+impl<'de> DeserializeSeed<'de> for CountingSeed<'_> {
+  type Value = Box<dyn Shape>;
+  fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+    self.count.set(self.count.get() + 1);
+    DeserializeTraitObject(self.registry).deserialize(deserializer)
+  }
+}
+
+impl Serialize for dyn Shape {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object(serializer, self.id(), self)
+  }
+}
+
+
+#[test]
+fn collect_errors_reports_every_element() {
+  let registry = registry();
+  let json = r##"[
+    {"Circle":{"radius":1}},
+    {"Square":{"side":3}},
+    {"Circle":{"radius":"large"}},
+    {"Label":{"text":"A","color":"#0080ff"}},
+    "Circle",
+    {"Label":{"text":"B","color":"red"}}
+  ]"##;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let collected = DeserializeVecWithTraitObjectCollectErrors(&registry).deserialize(&mut deserializer).unwrap();
+
+  let expected: Vec<Box<dyn Shape>> = vec![
+    Box::new(Circle { radius: 1 }),
+    Box::new(Label { text: "A".to_string(), color: Color([0, 128, 255]) }),
+  ];
+  assert_eq!(collected.value, expected);
+  let indices_and_ids: Vec<_> = collected.errors.iter().map(|e| (e.index, e.id)).collect();
+  assert_eq!(indices_and_ids, vec![(1, Some("Square")), (2, Some("Circle")), (4, None), (5, Some("Label"))]);
+  for ElementError { message, .. } in &collected.errors {
+    assert!(!message.is_empty());
+  }
+  assert!(collected.errors[0].message.contains("Square"), "{}", collected.errors[0]);
+  assert!(collected.errors[3].message.contains("#rrggbb"), "{}", collected.errors[3]);
+}
+
+#[test]
+fn collect_errors_not_human_readable() {
+  let registry = registry();
+  let bytes = rmp_serde::to_vec_named(&shapes()).unwrap();
+  let mut deserializer = rmp_serde::Deserializer::from_read_ref(&bytes);
+  let collected = DeserializeVecWithTraitObjectCollectErrors(&registry).deserialize(&mut deserializer).unwrap();
+  assert!(collected.errors.is_empty(), "{:?}", collected.errors);
+  assert_eq!(collected.value, shapes());
+}
+
+#[test]
+fn collect_errors_aborts_on_syntax_error() {
+  let registry = registry();
+  let mut deserializer = serde_json::Deserializer::from_str(r#"[{"Circle":{"radius":1}},{"Circle":"#);
+  assert!(DeserializeVecWithTraitObjectCollectErrors(&registry).deserialize(&mut deserializer).is_err());
+}
+
+#[test]
+fn seeds_btree_map() {
+  let registry = registry();
+  let json = r##"{"b":{"Circle":{"radius":2}},"a":{"Label":{"text":"A","color":"#0080ff"}}}"##;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let map: BTreeMap<String, Box<dyn Shape>> =
+    DeserializeMapWith::trait_object_value(&registry).deserialize(&mut deserializer).unwrap();
+  let keys: Vec<_> = map.keys().map(String::as_str).collect();
+  assert_eq!(keys, vec!["a", "b"]);
+  assert_eq!(map["b"].as_ref(), &Circle { radius: 2 } as &dyn Shape);
+}
+
+#[test]
+fn seeds_nested_sequences() {
+  let registry = registry();
+  let json = serde_json::to_string(&vec![shapes(), vec![], shapes()]).unwrap();
+  let mut deserializer = serde_json::Deserializer::from_str(&json);
+  let nested: VecDeque<Box<[Box<dyn Shape>]>> = DeserializeSeqWith::new(DeserializeSeqWith::trait_object(&registry))
+    .deserialize(&mut deserializer).unwrap();
+  assert_eq!(nested.len(), 3);
+  assert_eq!(nested[0].as_ref(), shapes().as_slice());
+  assert!(nested[1].is_empty());
+}
+
+#[test]
+fn seeds_array_of_options() {
+  let registry = registry();
+  let seed = DeserializeArrayWith::<_, 2>(DeserializeOptionWith(DeserializeTraitObject(&registry)));
+  let mut deserializer = serde_json::Deserializer::from_str(r#"[null,{"Circle":{"radius":2}}]"#);
+  let [first, second] = seed.deserialize(&mut deserializer).unwrap();
+  assert!(first.is_none());
+  assert_eq!(second.unwrap().as_ref(), &Circle { radius: 2 } as &dyn Shape);
+
+  let mut deserializer = serde_json::Deserializer::from_str(r#"[null]"#);
+  let error = seed.deserialize(&mut deserializer).unwrap_err();
+  assert!(error.to_string().contains("invalid length 1"), "{}", error);
+}
+
+#[test]
+fn seeds_not_human_readable() {
+  let registry = registry();
+  let bytes = rmp_serde::to_vec_named(&[shapes(), shapes()]).unwrap();
+  let mut deserializer = rmp_serde::Deserializer::from_read_ref(&bytes);
+  let array: [Vec<Box<dyn Shape>>; 2] = DeserializeArrayWith(DeserializeSeqWith::trait_object(&registry))
+    .deserialize(&mut deserializer).unwrap();
+  assert_eq!(array, [shapes(), shapes()]);
+}
+
+#[test]
+fn seeds_tuple_with_stateful_seeds() {
+  let registry = registry();
+  let count = Rc::new(Cell::new(0));
+  let counting = CountingSeed { registry: &registry, count: count.clone() };
+  let seed = DeserializeTupleWith((
+    DeserializeSeqWith::new(DeserializeSeqWith::new(counting.clone())),
+    DeserializeMapWith::new(PhantomData, counting.clone()),
+    DeserializeOptionWith(counting),
+  ));
+  let json = r#"[[[{"Circle":{"radius":1}}],[{"Circle":{"radius":2}}]],{"a":{"Circle":{"radius":3}}},null]"#;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let (nested, map, option): (Vec<Vec<_>>, HashMap<String, _>, Option<_>) =
+    seed.clone().deserialize(&mut deserializer).unwrap();
+  assert_eq!(nested.len(), 2);
+  assert_eq!(map["a"].as_ref(), &Circle { radius: 3 } as &dyn Shape);
+  assert!(option.is_none());
+  assert_eq!(count.get(), 3);
+
+  let mut deserializer = serde_json::Deserializer::from_str(r#"[[],{}]"#);
+  let result: Result<(Vec<Vec<_>>, HashMap<String, _>, Option<_>), _> = seed.deserialize(&mut deserializer);
+  assert!(result.unwrap_err().to_string().contains("invalid length 2"));
+}
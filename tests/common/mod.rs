@@ -0,0 +1,95 @@
+//! Fixture shared by the integration tests: a `Shape` trait with `Circle` and `Label` implementations, where `Label`
+//! has a `Color` that serializes differently in human-readable formats.
+
+// Each integration test uses a different subset of this fixture.
+#![allow(dead_code)]
+
+use std::any::Any;
+use std::fmt::Debug;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use serde_flexitos::{MapRegistry, Registry};
+
+pub trait Shape: erased_serde::Serialize + Debug {
+  fn id(&self) -> &'static str;
+  fn as_any(&self) -> &dyn Any;
+  fn dyn_eq(&self, other: &dyn Any) -> bool;
+}
+impl PartialEq for dyn Shape {
+  fn eq(&self, other: &Self) -> bool { self.dyn_eq(other.as_any()) }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Circle {
+  pub radius: u32,
+}
+impl Circle {
+  pub const ID: &'static str = "Circle";
+}
+impl Shape for Circle {
+  fn id(&self) -> &'static str { Self::ID }
+  fn as_any(&self) -> &dyn Any { self }
+  fn dyn_eq(&self, other: &dyn Any) -> bool { other.downcast_ref::<Self>() == Some(self) }
+}
+impl From<Circle> for Box<dyn Shape> {
+  fn from(value: Circle) -> Self { Box::new(value) }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Label {
+  pub text: String,
+  pub color: Color,
+}
+impl Label {
+  pub const ID: &'static str = "Label";
+}
+impl Shape for Label {
+  fn id(&self) -> &'static str { Self::ID }
+  fn as_any(&self) -> &dyn Any { self }
+  fn dyn_eq(&self, other: &dyn Any) -> bool { other.downcast_ref::<Self>() == Some(self) }
+}
+impl From<Label> for Box<dyn Shape> {
+  fn from(value: Label) -> Self { Box::new(value) }
+}
+
+/// Serializes as a `#rrggbb` string in human-readable formats, and as an `[r, g, b]` array otherwise.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Color(pub [u8; 3]);
+impl Serialize for Color {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+      let [r, g, b] = self.0;
+      serializer.serialize_str(&format!("#{:02x}{:02x}{:02x}", r, g, b))
+    } else {
+      self.0.serialize(serializer)
+    }
+  }
+}
+impl<'de> Deserialize<'de> for Color {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    if deserializer.is_human_readable() {
+      let string = String::deserialize(deserializer)?;
+      let hex = string.strip_prefix('#').filter(|hex| hex.len() == 6)
+        .ok_or_else(|| serde::de::Error::custom("expected a `#rrggbb` color"))?;
+      let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(serde::de::Error::custom);
+      Ok(Color([component(0)?, component(2)?, component(4)?]))
+    } else {
+      Ok(Color(<[u8; 3]>::deserialize(deserializer)?))
+    }
+  }
+}
+
+pub fn registry() -> MapRegistry<dyn Shape> {
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  registry.register_type::<Circle>(Circle::ID);
+  registry.register_type::<Label>(Label::ID);
+  registry
+}
+
+pub fn shapes() -> Vec<Box<dyn Shape>> {
+  vec![
+    Box::new(Circle { radius: 2 }),
+    Box::new(Label { text: "A".to_string(), color: Color([0, 128, 255]) }),
+  ]
+}
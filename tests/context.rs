@@ -0,0 +1,116 @@
+//! Tests for context-passing deserialization of trait objects.
+
+mod common;
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use serde::de::DeserializeSeed;
+use serde::{Serialize, Serializer};
+
+use serde_flexitos::{serialize_trait_object, GetError};
+use serde_flexitos::context::{
+  ContextMapRegistry, ContextRegistry, DeserializeTraitObjectWithCell, DeserializeVecWithTraitObjectWith,
+};
+use serde_flexitos::de::DeserializeMapWith;
+use common::{shapes, Circle, Label, Shape};
+
+/// Context recording the IDs of deserialized shapes.
+type Log = Vec<&'static str>;
+
+fn registry() -> ContextMapRegistry<dyn Shape, Log> {
+  let mut registry = ContextMapRegistry::<dyn Shape, Log>::new("Shape");
+  registry.register(Circle::ID, |d, log| {
+    log.push(Circle::ID);
+    Ok(Box::new(erased_serde::deserialize::<Circle>(d)?))
+  });
+  registry.register(Label::ID, |d, log| {
+    log.push(Label::ID);
+    Ok(Box::new(erased_serde::deserialize::<Label>(d)?))
+  });
+  registry
+}
+
+impl Serialize for dyn Shape {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object(serializer, self.id(), self)
+  }
+}
+
+
+#[test]
+fn context_single() {
+  let registry = registry();
+  let mut log = Log::new();
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"Circle":{"radius":2}}"#);
+  let deserialized = registry.deserialize_trait_object_with(&mut log, &mut deserializer).unwrap();
+  assert_eq!(deserialized.as_ref(), &Circle { radius: 2 } as &dyn Shape);
+  assert_eq!(log, vec![Circle::ID]);
+}
+
+#[test]
+fn context_vec() {
+  let registry = registry();
+  let log = RefCell::new(Log::new());
+  let json = serde_json::to_string(&shapes()).unwrap();
+  let mut deserializer = serde_json::Deserializer::from_str(&json);
+  let deserialized = DeserializeVecWithTraitObjectWith { registry: &registry, context: &log }
+    .deserialize(&mut deserializer).unwrap();
+  assert_eq!(deserialized, shapes());
+  assert_eq!(log.into_inner(), vec![Circle::ID, Label::ID]);
+}
+
+#[test]
+fn context_map() {
+  let registry = registry();
+  let log = RefCell::new(Log::new());
+  let json = r#"{"a":{"Circle":{"radius":2}},"b":{"Circle":{"radius":3}}}"#;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let deserialized: HashMap<String, Box<dyn Shape>> = DeserializeMapWith::trait_object_value_with(&registry, &log)
+    .deserialize(&mut deserializer).unwrap();
+  assert_eq!(deserialized["a"].as_ref(), &Circle { radius: 2 } as &dyn Shape);
+  assert_eq!(deserialized["b"].as_ref(), &Circle { radius: 3 } as &dyn Shape);
+  assert_eq!(log.into_inner(), vec![Circle::ID, Circle::ID]);
+}
+
+#[test]
+fn context_already_borrowed() {
+  let registry = registry();
+  let log = RefCell::new(Log::new());
+  let _borrow = log.borrow_mut();
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"Circle":{"radius":2}}"#);
+  let deserialized = DeserializeTraitObjectWithCell { registry: &registry, context: &log }
+    .deserialize(&mut deserializer);
+  assert!(deserialized.unwrap_err().to_string().contains("borrowed"));
+}
+
+#[test]
+fn context_borrowing() {
+  /// Context that borrows a counter, which is not `'static`.
+  struct Counter<'a>(&'a Cell<u32>);
+
+  let count = Cell::new(0);
+  let mut registry = ContextMapRegistry::<dyn Shape, Counter>::new("Shape");
+  registry.register_boxed(Circle::ID, Box::new(|d, counter: &mut Counter| {
+    counter.0.set(counter.0.get() + 1);
+    Ok(Box::new(erased_serde::deserialize::<Circle>(d)?))
+  }));
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"Circle":{"radius":2}}"#);
+  let deserialized = registry.deserialize_trait_object_with(&mut Counter(&count), &mut deserializer).unwrap();
+  assert_eq!(deserialized.as_ref(), &Circle { radius: 2 } as &dyn Shape);
+  assert_eq!(count.get(), 1);
+}
+
+#[test]
+fn context_multiple_registrations() {
+  let mut registry = registry();
+  let line = line!() + 1;
+  registry.register_boxed(Circle::ID, Box::new(|d, _| Ok(Box::new(erased_serde::deserialize::<Circle>(d)?))));
+  let Err(GetError::MultipleRegistrations { id, registrations }) = registry.get_deserialize_fn(Circle::ID) else {
+    panic!("expected multiple registrations");
+  };
+  assert_eq!(id, Circle::ID);
+  assert_eq!(registrations.len(), 2);
+  assert_eq!(registrations[1].location.line(), line);
+  assert!(registry.get_deserialize_fn(Label::ID).is_ok());
+}
@@ -0,0 +1,123 @@
+//! Round-trip and error tests for the string key representation of trait objects.
+
+mod common;
+
+use std::marker::PhantomData;
+
+use serde::de::DeserializeSeed;
+use serde::de::value::{BorrowedStrDeserializer, Error as ValueError};
+use serde::{Serialize, Serializer};
+
+use serde_flexitos::{serialize_trait_object_key, MapRegistry, Registry};
+use serde_flexitos::de::{DeserializeMapWith, DeserializeTraitObjectKey};
+use serde_flexitos::key::JsonKeyCodec;
+use serde_flexitos::ser::SerializeTraitObjectKey;
+use common::{registry, Circle, Color, Label, Shape};
+
+fn shapes() -> Vec<Box<dyn Shape>> {
+  vec![
+    Box::new(Circle { radius: 2 }),
+    // Quotes and the separator in the value are escaped in JSON keys.
+    Box::new(Label { text: "a \"quoted\" key: A".to_string(), color: Color([0, 128, 255]) }),
+  ]
+}
+
+impl Serialize for dyn Shape {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object_key(serializer, self.id(), self, JsonKeyCodec)
+  }
+}
+
+/// Serializes pairs as a map, as trait objects do not implement `Hash` or `Ord` here.
+struct Pairs<'a>(&'a [(Box<dyn Shape>, usize)]);
+impl Serialize for Pairs<'_> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(self.0.iter().map(|(key, value)| (key, value)))
+  }
+}
+
+
+#[test]
+fn key_roundtrip() {
+  let registry = registry();
+  for shape in shapes() {
+    let json = serde_json::to_string(shape.as_ref()).unwrap();
+    assert!(json.starts_with(&format!("\"{}:", shape.id())), "{}", json);
+
+    // JSON strings with escape sequences are not borrowed from the input.
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let roundtrip = registry.deserialize_trait_object_key(JsonKeyCodec, &mut deserializer).unwrap();
+    assert!(roundtrip.as_ref() == shape.as_ref());
+
+    let key: String = serde_json::from_str(&json).unwrap();
+    let deserializer = BorrowedStrDeserializer::<ValueError>::new(&key);
+    let roundtrip = registry.deserialize_trait_object_key(JsonKeyCodec, deserializer).unwrap();
+    assert!(roundtrip.as_ref() == shape.as_ref());
+  }
+}
+
+#[test]
+fn key_roundtrip_map() {
+  let registry = registry();
+  let pairs: Vec<(Box<dyn Shape>, usize)> = shapes().into_iter().zip(1..).collect();
+  let json = serde_json::to_string(&Pairs(&pairs)).unwrap();
+  let key_seed = DeserializeTraitObjectKey { registry: &registry, codec: JsonKeyCodec };
+  let seed = DeserializeMapWith::new(key_seed, PhantomData);
+  let mut deserializer = serde_json::Deserializer::from_str(&json);
+  let roundtrip: Vec<(Box<dyn Shape>, usize)> = seed.deserialize(&mut deserializer).unwrap();
+  assert_eq!(roundtrip, pairs);
+}
+
+#[test]
+fn key_roundtrip_non_string_id() {
+  let mut registry = MapRegistry::<dyn Shape, u32>::new("Shape");
+  registry.register_type::<Circle>(7);
+  registry.register_type::<Label>(8);
+
+  let circle = Circle { radius: 2 };
+  let serialize = SerializeTraitObjectKey { id: 7, trait_object: &circle, codec: JsonKeyCodec };
+  let json = serde_json::to_string(&serialize).unwrap();
+  assert_eq!(json, r#""7:{\"radius\":2}""#);
+  let mut deserializer = serde_json::Deserializer::from_str(&json);
+  let roundtrip = registry.deserialize_trait_object_key(JsonKeyCodec, &mut deserializer).unwrap();
+  assert!(roundtrip.as_ref() == &circle as &dyn Shape);
+
+  let deserializer = BorrowedStrDeserializer::<ValueError>::new(r##"8:{"text":"A","color":"#0080ff"}"##);
+  let roundtrip = registry.deserialize_trait_object_key(JsonKeyCodec, deserializer).unwrap();
+  assert!(roundtrip.as_ref() == &Label { text: "A".to_string(), color: Color([0, 128, 255]) } as &dyn Shape);
+}
+
+#[test]
+fn key_id_contains_separator() {
+  let circle = Circle { radius: 2 };
+  let serialize = SerializeTraitObjectKey { id: "Circle:Big", trait_object: &circle, codec: JsonKeyCodec };
+  let error = serde_json::to_string(&serialize).unwrap_err();
+  assert!(error.to_string().contains("contains key separator"), "{}", error);
+}
+
+#[test]
+fn key_unknown_id() {
+  let registry = registry();
+  let deserializer = BorrowedStrDeserializer::<ValueError>::new(r#"Square:{"side":3}"#);
+  let error = registry.deserialize_trait_object_key(JsonKeyCodec, deserializer).unwrap_err();
+  assert!(error.to_string().contains("no deserialize function was registered for id '\"Square\"'"), "{}", error);
+
+  // IDs are matched exactly, not by prefix.
+  let deserializer = BorrowedStrDeserializer::<ValueError>::new(r#"Circ:{"radius":2}"#);
+  assert!(registry.deserialize_trait_object_key(JsonKeyCodec, deserializer).is_err());
+}
+
+#[test]
+fn key_missing_separator() {
+  let registry = registry();
+  let deserializer = BorrowedStrDeserializer::<ValueError>::new(Circle::ID);
+  let error = registry.deserialize_trait_object_key(JsonKeyCodec, deserializer).unwrap_err();
+  assert!(error.to_string().contains("an `id:value` string for `Box<dyn Shape>`"), "{}", error);
+}
+
+#[test]
+fn key_trailing_characters() {
+  let registry = registry();
+  let deserializer = BorrowedStrDeserializer::<ValueError>::new(r#"Circle:{"radius":2} 3"#);
+  assert!(registry.deserialize_trait_object_key(JsonKeyCodec, deserializer).is_err());
+}
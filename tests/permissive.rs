@@ -0,0 +1,255 @@
+//! Tests for permissive deserialization of trait objects.
+
+mod common;
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+use std::marker::PhantomData;
+
+use serde::de::{DeserializeSeed, SeqAccess, Visitor};
+use serde::{Deserializer, Serialize, Serializer};
+
+use serde_flexitos::{serialize_trait_object, GetError, MapRegistry, Registry};
+use serde_flexitos::permissive::{
+  PermissiveDeserializeMapWith, PermissiveDeserializeTraitObject, PermissiveDeserializeTraitObjectWithDiagnostics,
+  PermissiveDeserializeVecWithTraitObject, PreservingDeserializeTraitObject, Reason, Required, Skipped, Unknown,
+};
+use common::Circle;
+
+/// Like `common::Shape`, but returns an ID borrowing from `self`, so that it can be implemented for [`Unknown`].
+trait Shape: erased_serde::Serialize + Debug {
+  fn id(&self) -> &str;
+  fn as_any(&self) -> &dyn Any;
+  fn dyn_eq(&self, other: &dyn Any) -> bool;
+}
+impl PartialEq for dyn Shape {
+  fn eq(&self, other: &Self) -> bool { self.dyn_eq(other.as_any()) }
+}
+impl Eq for dyn Shape {}
+impl Hash for dyn Shape {
+  fn hash<H: Hasher>(&self, state: &mut H) { self.id().hash(state) }
+}
+impl Serialize for dyn Shape {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object(serializer, self.id(), self)
+  }
+}
+
+impl Shape for Circle {
+  fn id(&self) -> &str { Self::ID }
+  fn as_any(&self) -> &dyn Any { self }
+  fn dyn_eq(&self, other: &dyn Any) -> bool { other.downcast_ref::<Self>() == Some(self) }
+}
+impl From<Circle> for Box<dyn Shape> {
+  fn from(value: Circle) -> Self { Box::new(value) }
+}
+
+impl Shape for Unknown {
+  fn id(&self) -> &str { Unknown::<String>::id(self) }
+  fn as_any(&self) -> &dyn Any { self }
+  fn dyn_eq(&self, other: &dyn Any) -> bool { other.downcast_ref::<Self>() == Some(self) }
+}
+impl From<Unknown> for Box<dyn Shape> {
+  fn from(value: Unknown) -> Self { Box::new(value) }
+}
+
+fn registry() -> MapRegistry<dyn Shape> {
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  registry.register_type::<Circle>(Circle::ID);
+  registry
+}
+
+
+#[test]
+fn permissive_unknown_id_consumes_value() {
+  let registry = registry();
+  let json = r#"[{"Square":{"side":3,"tags":["a"]}},{"Circle":{"radius":2}}]"#;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let deserialized = (&mut deserializer).deserialize_seq(Elements(&registry)).unwrap();
+  assert!(deserialized[0].is_none());
+  assert_eq!(deserialized[1].as_deref(), Some(&Circle { radius: 2 } as &dyn Shape));
+}
+
+/// Deserializes a sequence of optional trait objects with [`PermissiveDeserializeTraitObject`].
+struct Elements<'r>(&'r MapRegistry<dyn Shape>);
+impl<'de> Visitor<'de> for Elements<'_> {
+  type Value = Vec<Option<Box<dyn Shape>>>;
+  fn expecting(&self, formatter: &mut Formatter) -> fmt::Result { formatter.write_str("a sequence") }
+  fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+    let mut vec = Vec::new();
+    while let Some(element) = seq.next_element_seed(PermissiveDeserializeTraitObject(self.0))? {
+      vec.push(element);
+    }
+    Ok(vec)
+  }
+}
+
+#[test]
+fn permissive_vec_skips_unknown_ids() {
+  let registry = registry();
+  let json = r#"[{"Square":{"side":3}},
+    {"Circle":{"radius":1}},{"Hexagon":[1,[2]]},{"Circle":{"radius":2}},{"Star":0}]"#;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let deserialized = PermissiveDeserializeVecWithTraitObject::new(&registry).deserialize(&mut deserializer).unwrap();
+  assert_eq!(deserialized.skipped, 3);
+  let expected: Vec<Box<dyn Shape>> = vec![Box::new(Circle { radius: 1 }), Box::new(Circle { radius: 2 })];
+  assert_eq!(deserialized.value, expected);
+}
+
+#[test]
+fn permissive_map_skips_unknown_values() {
+  let registry = registry();
+  let json = r#"{"a":{"Circle":{"radius":1}},"b":{"Square":{"side":3}},"c":{"Circle":{"radius":2}},"d":{"Star":[]}}"#;
+
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let deserialized: Skipped<BTreeMap<String, Box<dyn Shape>>> =
+    PermissiveDeserializeMapWith::trait_object_value(&registry).deserialize(&mut deserializer).unwrap();
+  assert_eq!(deserialized.skipped, 2);
+  let expected: BTreeMap<String, Box<dyn Shape>> = BTreeMap::from([
+    ("a".to_string(), Box::new(Circle { radius: 1 }) as Box<dyn Shape>),
+    ("c".to_string(), Box::new(Circle { radius: 2 })),
+  ]);
+  assert_eq!(deserialized.value, expected);
+
+  // Any map that implements `Default` and `Extend`, such as a `HashMap` with a custom hasher.
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let deserialized: Skipped<HashMap<String, Box<dyn Shape>, BuildHasherDefault<DefaultHasher>>> =
+    PermissiveDeserializeMapWith::trait_object_value(&registry).deserialize(&mut deserializer).unwrap();
+  assert_eq!(deserialized.skipped, 2);
+  assert_eq!(deserialized.value.len(), 2);
+  assert_eq!(deserialized.value["c"].as_ref(), &Circle { radius: 2 } as &dyn Shape);
+}
+
+#[test]
+fn permissive_map_skips_unknown_keys() {
+  let registry = registry();
+  // Trait objects as keys require a format that supports non-string keys.
+  let ron = r#"{{"Circle":(radius:1)}:"a",{"Square":(side:3)}:"b",{"Circle":(radius:2)}:"c"}"#;
+  let mut deserializer = ron::Deserializer::from_str(ron).unwrap();
+  let deserialized: Skipped<HashMap<Box<dyn Shape>, String>> = PermissiveDeserializeMapWith::trait_object_key(&registry)
+    .deserialize(&mut deserializer).unwrap();
+  assert_eq!(deserialized.skipped, 1);
+  let expected: HashMap<Box<dyn Shape>, String> = HashMap::from([
+    (Box::new(Circle { radius: 1 }) as Box<dyn Shape>, "a".to_string()),
+    (Box::new(Circle { radius: 2 }), "c".to_string()),
+  ]);
+  assert_eq!(deserialized.value, expected);
+}
+
+
+/// Reported diagnostic: trait object name, ID, and whether getting the deserialize function failed with a
+/// not-registered error, a multiple-registrations error, or whether deserializing failed.
+#[derive(PartialEq, Debug)]
+struct Report(&'static str, &'static str, &'static str);
+
+fn report_into(reports: &RefCell<Vec<Report>>) -> impl Fn(&'static str, &&'static str, Reason<&'static str>) + '_ {
+  |trait_object_name, id, reason| {
+    let kind = match reason {
+      Reason::Get(GetError::NotRegistered { .. }) => "not registered",
+      Reason::Get(GetError::MultipleRegistrations { .. }) => "multiple registrations",
+      Reason::Deserialize(_) => "deserialize",
+    };
+    reports.borrow_mut().push(Report(trait_object_name, id, kind));
+  }
+}
+
+#[test]
+fn diagnostics_not_registered() {
+  let registry = registry();
+  let reports = RefCell::new(Vec::new());
+  let report = report_into(&reports);
+  let json = r#"[{"Square":{"side":3}},{"Circle":{"radius":1}},{"Star":[]}]"#;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let deserialized = PermissiveDeserializeVecWithTraitObject::with_diagnostics(&registry, &report)
+    .deserialize(&mut deserializer).unwrap();
+  assert_eq!(deserialized.skipped, 2);
+  assert_eq!(deserialized.value, vec![Box::new(Circle { radius: 1 }) as Box<dyn Shape>]);
+  assert_eq!(*reports.borrow(), vec![
+    Report("Shape", "Square", "not registered"),
+    Report("Shape", "Star", "not registered"),
+  ]);
+}
+
+#[test]
+fn diagnostics_deserialize_failed() {
+  let registry = registry();
+  let reports = RefCell::new(Vec::new());
+  let report = report_into(&reports);
+  let json = r#"{"Circle":{"radius":"large"}}"#;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let deserialized = PermissiveDeserializeTraitObjectWithDiagnostics { registry: &registry, diagnostics: &report }
+    .deserialize(&mut deserializer);
+  assert!(deserialized.is_err());
+  assert_eq!(*reports.borrow(), vec![Report("Shape", "Circle", "deserialize")]);
+}
+
+#[test]
+fn diagnostics_multiple_registrations() {
+  let mut registry = registry();
+  registry.register_type::<Circle>(Circle::ID);
+  let reports = RefCell::new(Vec::new());
+  let report = report_into(&reports);
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"Circle":{"radius":1}}"#);
+  let deserialized = PermissiveDeserializeTraitObjectWithDiagnostics { registry: &registry, diagnostics: &report }
+    .deserialize(&mut deserializer);
+  assert!(deserialized.is_err());
+  assert_eq!(*reports.borrow(), vec![Report("Shape", "Circle", "multiple registrations")]);
+}
+
+#[test]
+fn diagnostics_map_with_required_keys() {
+  let registry = registry();
+  let reports = RefCell::new(Vec::new());
+  let report = report_into(&reports);
+  let json = r#"{"a":{"Circle":{"radius":1}},"b":{"Square":{"side":3}}}"#;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let value = PermissiveDeserializeTraitObjectWithDiagnostics { registry: &registry, diagnostics: &report };
+  let deserialized: Skipped<BTreeMap<String, Box<dyn Shape>>> =
+    PermissiveDeserializeMapWith::new(Required(PhantomData), value).deserialize(&mut deserializer).unwrap();
+  assert_eq!(deserialized.skipped, 1);
+  assert_eq!(deserialized.value["a"].as_ref(), &Circle { radius: 1 } as &dyn Shape);
+  assert_eq!(*reports.borrow(), vec![Report("Shape", "Square", "not registered")]);
+}
+
+#[test]
+fn preserving_known_id() {
+  let registry = registry();
+  let json = r#"{"Circle":{"radius":2}}"#;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let deserialized = PreservingDeserializeTraitObject::new(&registry).deserialize(&mut deserializer).unwrap();
+  assert_eq!(deserialized.as_ref(), &Circle { radius: 2 } as &dyn Shape);
+  assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+}
+
+#[test]
+fn preserving_unknown_id() {
+  let registry = registry();
+  let json = r#"{"Square":{"side":3,"tags":["a",null,{"b":-1.5}],"data":[true]}}"#;
+  let mut deserializer = serde_json::Deserializer::from_str(json);
+  let deserialized = PreservingDeserializeTraitObject::new(&registry).deserialize(&mut deserializer).unwrap();
+  let unknown = deserialized.as_any().downcast_ref::<Unknown>().unwrap();
+  assert_eq!(unknown.id(), "Square");
+  assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+}
+
+#[test]
+fn preserving_unknown_id_roundtrip() {
+  let registry = registry();
+  let shapes: Vec<Box<dyn Shape>> = [r#"{"Circle":{"radius":2}}"#, r#"{"Square":{"side":3}}"#].into_iter()
+    .map(|json| {
+      let mut deserializer = serde_json::Deserializer::from_str(json);
+      PreservingDeserializeTraitObject::new(&registry).deserialize(&mut deserializer).unwrap()
+    })
+    .collect();
+  let json = serde_json::to_string(&shapes).unwrap();
+  assert_eq!(json, r#"[{"Circle":{"radius":2}},{"Square":{"side":3}}]"#);
+
+  // Deserializing the serialized unknown trait object again results in an equal trait object.
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"Square":{"side":3}}"#);
+  let again = PreservingDeserializeTraitObject::new(&registry).deserialize(&mut deserializer).unwrap();
+  assert_eq!(again.as_ref(), shapes[1].as_ref());
+}
@@ -0,0 +1,204 @@
+//! Tests for registering deserialize functions and getting them from registries.
+
+mod common;
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use serde::{Serialize, Serializer};
+
+use serde_flexitos::{
+  serialize_trait_object, serialize_trait_object_by_type_id, DynDeserializeFn, GetError, MapRegistry, Registry,
+  RegistrationInfo,
+};
+use common::{registry, shapes, Circle, Color, Label, Shape};
+
+impl Serialize for dyn Shape {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object(serializer, self.id(), self)
+  }
+}
+
+
+#[test]
+fn register_closure_captures_state() {
+  let count = Arc::new(AtomicU32::new(0));
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  let counter = count.clone();
+  // Assign each deserialized circle a radius from a counter, instead of from the serialized data.
+  registry.register_closure(Circle::ID, move |deserializer| {
+    erased_serde::deserialize::<()>(deserializer)?;
+    Ok(Box::new(Circle { radius: counter.fetch_add(1, Ordering::Relaxed) }))
+  });
+  registry.register_type::<Label>(Label::ID);
+
+  let json = r##"[{"Circle":null},{"Label":{"text":"A","color":"#0080ff"}},{"Circle":null}]"##;
+  let values: Vec<serde_json::Value> = serde_json::from_str(json).unwrap();
+  let deserialized: Vec<Box<dyn Shape>> = values.iter()
+    .map(|value| registry.deserialize_trait_object(value).unwrap())
+    .collect();
+  let expected: Vec<Box<dyn Shape>> = vec![
+    Box::new(Circle { radius: 0 }),
+    Box::new(Label { text: "A".to_string(), color: Color([0, 128, 255]) }),
+    Box::new(Circle { radius: 1 }),
+  ];
+  assert_eq!(deserialized, expected);
+  assert_eq!(count.load(Ordering::Relaxed), 2);
+}
+
+/// Asserts that `info` is the registration of a type ending with `type_name` (if any) on `line` of this file.
+fn assert_registration(info: &RegistrationInfo, type_name: Option<&str>, line: u32) {
+  assert_eq!(info.type_name.map(|name| name.rsplit("::").next().unwrap()), type_name);
+  assert_eq!(info.location.file(), file!());
+  assert_eq!(info.location.line(), line);
+}
+
+#[test]
+fn multiple_registrations_report_registrations() {
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  let line = line!() + 1;
+  registry.register_type::<Circle>(Circle::ID);
+  registry.register_type::<Label>(Circle::ID);
+  registry.register(Circle::ID, |d| Ok(Box::new(erased_serde::deserialize::<Circle>(d)?)));
+  registry.register_type::<Label>(Label::ID);
+
+  let Err(GetError::MultipleRegistrations { id, registrations }) = registry.get_deserialize_fn(Circle::ID) else {
+    panic!("expected multiple registrations");
+  };
+  assert_eq!(id, Circle::ID);
+  assert_eq!(registrations.len(), 3);
+  assert_registration(&registrations[0], Some("Circle"), line);
+  assert_registration(&registrations[1], Some("Label"), line + 1);
+  assert_registration(&registrations[2], None, line + 2);
+  assert!(registry.get_deserialize_fn(Label::ID).is_ok());
+
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"Circle":{"radius":1}}"#);
+  let error = registry.deserialize_trait_object(&mut deserializer).err().unwrap();
+  assert!(error.to_string().contains(&registrations[1].to_string()), "{}", error);
+
+  // Lookups by compact ID report the same registrations.
+  let error = registry.get_deserialize_fn_by_compact_id(serde_flexitos::compact_id(Circle::ID)).err().unwrap();
+  assert!(matches!(error, GetError::MultipleRegistrations { registrations: r, .. } if r == registrations));
+}
+
+#[test]
+fn register_closure_multiple_registrations() {
+  let mut registry = registry();
+  registry.register_closure(Circle::ID, |_| Ok(Box::new(Circle { radius: 0 })));
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"Circle":{"radius":1}}"#);
+  let error = registry.deserialize_trait_object(&mut deserializer).err().unwrap();
+  assert!(error.to_string().contains("multiple deserialize functions were registered"), "{}", error);
+}
+
+/// Registry implementing only the required methods, using the provided `register` for function pointers.
+struct ListRegistry(BTreeMap<&'static str, Box<DynDeserializeFn<dyn Shape>>>);
+impl Registry for ListRegistry {
+  type Identifier = &'static str;
+  type TraitObject = dyn Shape;
+  fn register_boxed(&mut self, id: &'static str, deserialize_fn: Box<DynDeserializeFn<dyn Shape>>) {
+    self.0.insert(id, deserialize_fn);
+  }
+  fn get_deserialize_fn(&self, id: &'static str) -> Result<&DynDeserializeFn<dyn Shape>, GetError<&'static str>> {
+    self.0.get(id).map(|deserialize_fn| deserialize_fn.as_ref()).ok_or(GetError::NotRegistered { id })
+  }
+  fn get_trait_object_name(&self) -> &'static str { "Shape" }
+}
+
+#[test]
+fn register_provided_for_custom_registry() {
+  let mut registry = ListRegistry(BTreeMap::new());
+  registry.register(Circle::ID, |deserializer| Ok(Box::new(erased_serde::deserialize::<Circle>(deserializer)?)));
+  registry.register_type::<Label>(Label::ID);
+  let jsons = [r#"{"Circle":{"radius":2}}"#, r##"{"Label":{"text":"A","color":"#0080ff"}}"##];
+  for (json, shape) in jsons.into_iter().zip(shapes()) {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let deserialized = registry.deserialize_trait_object(&mut deserializer).unwrap();
+    assert_eq!(deserialized.as_ref(), shape.as_ref());
+  }
+}
+
+/// Serializes `shape` with the ID registered in `registry` for the `TypeId` of its concrete type.
+fn to_json_by_type_id(registry: &MapRegistry<dyn Shape>, shape: &dyn Shape) -> Result<String, serde_json::Error> {
+  let mut json = Vec::new();
+  let mut serializer = serde_json::Serializer::new(&mut json);
+  serialize_trait_object_by_type_id(&mut serializer, registry, shape.as_any().type_id(), shape)?;
+  Ok(String::from_utf8(json).unwrap())
+}
+
+#[test]
+fn type_id_roundtrip() {
+  let registry = registry();
+  for shape in shapes() {
+    let json = to_json_by_type_id(&registry, shape.as_ref()).unwrap();
+    assert_eq!(json, serde_json::to_string(&shape).unwrap());
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let deserialized = registry.deserialize_trait_object(&mut deserializer).unwrap();
+    assert_eq!(deserialized.as_ref(), shape.as_ref());
+  }
+}
+
+#[test]
+fn type_id_not_registered() {
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  // Closures are not registered for a type, so they do not register a `TypeId`.
+  registry.register_closure(Circle::ID, |_| Ok(Box::new(Circle { radius: 0 })));
+  let error = to_json_by_type_id(&registry, &Circle { radius: 2 }).unwrap_err();
+  assert!(error.to_string().contains("no id was registered for type id"), "{}", error);
+  assert!(error.to_string().contains("`dyn Shape`"), "{}", error);
+
+  // Custom registries do not support `TypeId`s by default.
+  let mut registry = ListRegistry(BTreeMap::new());
+  registry.register_type::<Circle>(Circle::ID);
+  let error = registry.get_id_by_type_id(std::any::TypeId::of::<Circle>()).unwrap_err();
+  assert!(matches!(error, GetError::NotRegistered { .. }));
+}
+
+#[test]
+fn type_id_multiple_ids() {
+  let mut registry = registry();
+  // Registering the same ID again keeps the `TypeId` registration, but a different ID makes it ambiguous.
+  registry.register_type::<Label>(Label::ID);
+  assert_eq!(registry.get_id_by_type_id(std::any::TypeId::of::<Label>()).ok(), Some(&Label::ID));
+  let line = line!() + 1;
+  registry.register_type::<Circle>("Round");
+  let error = to_json_by_type_id(&registry, &Circle { radius: 2 }).unwrap_err();
+  assert!(error.to_string().contains("multiple ids were registered for type id"), "{}", error);
+
+  let type_id = std::any::TypeId::of::<Circle>();
+  let Err(GetError::MultipleRegistrations { registrations, .. }) = registry.get_id_by_type_id(type_id) else {
+    panic!("expected multiple registrations");
+  };
+  assert_eq!(registrations.len(), 2);
+  assert_registration(&registrations[1], Some("Circle"), line);
+  assert!(error.to_string().contains(&registrations[1].to_string()), "{}", error);
+}
+
+#[test]
+fn introspection() {
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  assert!(registry.is_empty());
+  registry.register_type::<Label>(Label::ID);
+  registry.register_type::<Circle>(Circle::ID);
+  assert_eq!(registry.len(), 2);
+  assert!(!registry.is_empty());
+  assert!(registry.contains(&Circle::ID));
+  assert!(!registry.contains(&"Square"));
+  assert_eq!(registry.get_ids().copied().collect::<Vec<_>>(), vec![Circle::ID, Label::ID]);
+  assert_eq!(registry.get_ids_with_multiple_registrations().count(), 0);
+
+  registry.register_closure(Label::ID, |_| Ok(Box::new(Circle { radius: 0 })));
+  assert_eq!(registry.len(), 2);
+  assert!(registry.contains(&Label::ID));
+  assert_eq!(registry.get_ids_with_multiple_registrations().copied().collect::<Vec<_>>(), vec![Label::ID]);
+}
+
+#[test]
+fn introspection_unsupported_for_custom_registry() {
+  let mut registry = ListRegistry(BTreeMap::new());
+  registry.register_type::<Circle>(Circle::ID);
+  // The default implementations are based on `get_ids`, which returns no IDs by default.
+  assert!(registry.is_empty());
+  assert!(!registry.contains(&Circle::ID));
+  assert_eq!(registry.get_ids_with_multiple_registrations().count(), 0);
+}
@@ -0,0 +1,390 @@
+//! Round-trip and error tests for the representations of trait objects.
+
+mod common;
+
+use std::collections::BTreeMap;
+
+use bincode::Options;
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize, Serializer};
+
+use serde_flexitos::{
+  compact_id, serialize_trait_object_untagged, serialize_trait_object_variant, MapRegistry, Registry,
+};
+use serde_flexitos::de::DeserializeTraitObjectUntagged;
+use serde_flexitos::ser::{
+  SerializeTraitObjectAdjacentlyTagged, SerializeTraitObjectCompact, SerializeTraitObjectInternallyTagged,
+  SerializeTraitObjectTuple,
+};
+use common::{shapes, Circle, Color, Label, Shape};
+
+fn registry() -> MapRegistry<dyn Shape> {
+  let mut registry = common::registry();
+  registry.set_variants(&[Circle::ID, Label::ID]);
+  registry
+}
+
+
+#[test]
+fn internally_tagged_roundtrip() {
+  let registry = registry();
+  for shape in shapes() {
+    let serialize = SerializeTraitObjectInternallyTagged { tag: "type", id: shape.id(), trait_object: shape.as_ref() };
+    let json = serde_json::to_string(&serialize).unwrap();
+    assert!(json.starts_with(&format!("{{\"type\":\"{}\",", shape.id())), "{}", json);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let roundtrip = registry.deserialize_trait_object_internally_tagged("type", &mut deserializer).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+  }
+}
+
+#[test]
+fn internally_tagged_roundtrip_not_human_readable() {
+  let registry = registry();
+  for shape in shapes() {
+    let serialize = SerializeTraitObjectInternallyTagged { tag: "type", id: shape.id(), trait_object: shape.as_ref() };
+    let bytes = rmp_serde::to_vec_named(&serialize).unwrap();
+    let mut deserializer = rmp_serde::Deserializer::from_read_ref(&bytes);
+    let roundtrip = registry.deserialize_trait_object_internally_tagged("type", &mut deserializer).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+  }
+}
+
+#[test]
+fn internally_tagged_tag_not_first() {
+  let registry = registry();
+  let mut deserializer = serde_json::Deserializer::from_str(r##"{"text":"B","color":"#ff0000","type":"Label"}"##);
+  let deserialized = registry.deserialize_trait_object_internally_tagged("type", &mut deserializer).unwrap();
+  let expected: Box<dyn Shape> = Box::new(Label { text: "B".to_string(), color: Color([255, 0, 0]) });
+  assert_eq!(deserialized.as_ref(), expected.as_ref());
+}
+
+#[test]
+fn internally_tagged_missing_tag() {
+  let registry = registry();
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"radius":2}"#);
+  let error = registry.deserialize_trait_object_internally_tagged("type", &mut deserializer).unwrap_err();
+  assert!(error.to_string().contains("missing field `type`"), "{}", error);
+}
+
+#[test]
+fn internally_tagged_unknown_id() {
+  let registry = registry();
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"type":"Square","radius":2}"#);
+  let error = registry.deserialize_trait_object_internally_tagged("type", &mut deserializer).unwrap_err();
+  assert!(error.to_string().contains("\"Square\""), "{}", error);
+}
+
+#[test]
+fn internally_tagged_field_clashes_with_tag() {
+  #[derive(Serialize)]
+  struct Typed {
+    r#type: String,
+  }
+  let typed: &dyn erased_serde::Serialize = &Typed { r#type: "A".to_string() };
+  let serialize = SerializeTraitObjectInternallyTagged { tag: "type", id: "Typed", trait_object: typed };
+  let error = serde_json::to_string(&serialize).unwrap_err();
+  assert!(error.to_string().contains("field named like the tag `type`"), "{}", error);
+}
+
+#[test]
+fn adjacently_tagged_roundtrip() {
+  let registry = registry();
+  for shape in shapes() {
+    let serialize =
+      SerializeTraitObjectAdjacentlyTagged { tag: "t", content: "c", id: shape.id(), trait_object: shape.as_ref() };
+    let json = serde_json::to_string(&serialize).unwrap();
+    assert!(json.starts_with(&format!("{{\"t\":\"{}\",\"c\":", shape.id())), "{}", json);
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let roundtrip = registry.deserialize_trait_object_adjacently_tagged("t", "c", &mut deserializer).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+  }
+}
+
+#[test]
+fn adjacently_tagged_roundtrip_bincode() {
+  let registry = registry();
+  for shape in shapes() {
+    let serialize =
+      SerializeTraitObjectAdjacentlyTagged { tag: "t", content: "c", id: shape.id(), trait_object: shape.as_ref() };
+    let bytes = bincode::options().serialize(&serialize).unwrap();
+    let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+    let roundtrip = registry.deserialize_trait_object_adjacently_tagged("t", "c", &mut deserializer).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+  }
+}
+
+#[test]
+fn adjacently_tagged_content_before_tag() {
+  let registry = registry();
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"c":{"radius":3},"t":"Circle"}"#);
+  let deserialized = registry.deserialize_trait_object_adjacently_tagged("t", "c", &mut deserializer).unwrap();
+  assert_eq!(deserialized.as_ref(), &Circle { radius: 3 } as &dyn Shape);
+}
+
+#[test]
+fn adjacently_tagged_content_before_tag_not_human_readable() {
+  #[derive(Serialize)]
+  struct ContentFirst<'a> {
+    c: &'a Label,
+    t: &'a str,
+  }
+  let label = Label { text: "C".to_string(), color: Color([1, 2, 3]) };
+  let bytes = rmp_serde::to_vec_named(&ContentFirst { c: &label, t: Label::ID }).unwrap();
+  let mut deserializer = rmp_serde::Deserializer::from_read_ref(&bytes);
+  let deserialized = registry().deserialize_trait_object_adjacently_tagged("t", "c", &mut deserializer).unwrap();
+  assert_eq!(deserialized.as_ref(), &label as &dyn Shape);
+}
+
+#[test]
+fn adjacently_tagged_missing_tag_or_content() {
+  let registry = registry();
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"c":{"radius":3}}"#);
+  let error = registry.deserialize_trait_object_adjacently_tagged("t", "c", &mut deserializer).unwrap_err();
+  assert!(error.to_string().contains("missing field `t`"), "{}", error);
+
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"t":"Circle"}"#);
+  let error = registry.deserialize_trait_object_adjacently_tagged("t", "c", &mut deserializer).unwrap_err();
+  assert!(error.to_string().contains("missing field `c`"), "{}", error);
+}
+
+#[test]
+fn adjacently_tagged_unknown_id() {
+  let registry = registry();
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"t":"Square","c":{"radius":3}}"#);
+  let error = registry.deserialize_trait_object_adjacently_tagged("t", "c", &mut deserializer).unwrap_err();
+  assert!(error.to_string().contains("\"Square\""), "{}", error);
+}
+
+#[test]
+fn tuple_roundtrip() {
+  let registry = registry();
+  for shape in shapes() {
+    let serialize = SerializeTraitObjectTuple { id: shape.id(), trait_object: shape.as_ref() };
+
+    let json = serde_json::to_string(&serialize).unwrap();
+    assert!(json.starts_with(&format!("[\"{}\",", shape.id())), "{}", json);
+    let roundtrip = registry.deserialize_trait_object_tuple(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+
+    let bytes = bincode::options().serialize(&serialize).unwrap();
+    let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+    let roundtrip = registry.deserialize_trait_object_tuple(&mut deserializer).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+  }
+}
+
+#[test]
+fn tuple_missing_elements() {
+  let registry = registry();
+  let error = registry.deserialize_trait_object_tuple(&mut serde_json::Deserializer::from_str("[]")).unwrap_err();
+  assert!(error.to_string().contains("invalid length 0"), "{}", error);
+
+  let mut deserializer = serde_json::Deserializer::from_str(r#"["Circle"]"#);
+  let error = registry.deserialize_trait_object_tuple(&mut deserializer).unwrap_err();
+  assert!(error.to_string().contains("invalid length 1"), "{}", error);
+}
+
+#[test]
+fn tuple_unknown_id() {
+  let registry = registry();
+  let mut deserializer = serde_json::Deserializer::from_str(r#"["Square",{"radius":3}]"#);
+  let error = registry.deserialize_trait_object_tuple(&mut deserializer).unwrap_err();
+  assert!(error.to_string().contains("\"Square\""), "{}", error);
+}
+
+/// Serializes a trait object as an enum variant, as that requires the registry.
+struct Variant<'a>(&'a MapRegistry<dyn Shape>, &'static str, &'a dyn Shape);
+impl Serialize for Variant<'_> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object_variant(serializer, self.0, self.1, self.2)
+  }
+}
+
+#[test]
+fn variant_roundtrip() {
+  let registry = registry();
+  for shape in shapes() {
+    let serialize = Variant(&registry, shape.id(), shape.as_ref());
+
+    let ron = ron::to_string(&serialize).unwrap();
+    assert!(ron.starts_with(&format!("{}(", shape.id())), "{}", ron);
+    let roundtrip = registry.deserialize_trait_object_variant(&mut ron::Deserializer::from_str(&ron).unwrap()).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+
+    let bytes = bincode::options().serialize(&serialize).unwrap();
+    let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+    let roundtrip = registry.deserialize_trait_object_variant(&mut deserializer).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+  }
+}
+
+#[test]
+fn variant_index_is_position() {
+  let circle = Circle { radius: 4 };
+  let mut registry = registry();
+  let bytes = bincode::options().serialize(&Variant(&registry, Circle::ID, &circle)).unwrap();
+  assert_eq!(bytes, bincode::options().serialize(&(0u32, &circle)).unwrap());
+
+  // Appending variants does not change variant indices, so previously serialized data remains readable.
+  registry.register_type::<Label>("ALabelSortedBeforeCircle");
+  registry.set_variants(&[Circle::ID, Label::ID, "ALabelSortedBeforeCircle"]);
+  let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+  let deserialized = registry.deserialize_trait_object_variant(&mut deserializer).unwrap();
+  assert_eq!(deserialized.as_ref(), &circle as &dyn Shape);
+}
+
+#[test]
+fn variant_unknown() {
+  let registry = registry();
+  let mut deserializer = ron::Deserializer::from_str("Square((radius:3))").unwrap();
+  let error = registry.deserialize_trait_object_variant(&mut deserializer).unwrap_err();
+  assert!(error.to_string().contains("Square"), "{}", error);
+  assert!(error.to_string().contains("`Circle` or `Label`"), "{}", error);
+
+  let bytes = bincode::options().serialize(&(2u32, Circle { radius: 3 })).unwrap();
+  let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+  let error = registry.deserialize_trait_object_variant(&mut deserializer).unwrap_err();
+  assert!(error.to_string().contains("invalid value: integer `2`"), "{}", error);
+
+  let circle = Circle { radius: 3 };
+  let error = ron::to_string(&Variant(&registry, "Square", &circle)).unwrap_err();
+  assert!(error.to_string().contains("cannot serialize `Shape` variant 'Square'"), "{}", error);
+}
+
+#[test]
+fn variant_not_in_variants() {
+  // Registered, but not one of the variants, so it has no variant index.
+  let mut registry = registry();
+  registry.register_type::<Circle>("Square");
+  let circle = Circle { radius: 5 };
+  let error = bincode::options().serialize(&Variant(&registry, "Square", &circle)).unwrap_err();
+  assert!(error.to_string().contains("not one of the variants of the registry"), "{}", error);
+
+  // Without variants, the enum variant representation is unsupported.
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  registry.register_type::<Circle>(Circle::ID);
+  let error = ron::to_string(&Variant(&registry, Circle::ID, &circle)).unwrap_err();
+  assert!(error.to_string().contains("not one of the variants of the registry"), "{}", error);
+}
+
+#[test]
+fn compact_roundtrip() {
+  let registry = registry();
+  for shape in shapes() {
+    let serialize = SerializeTraitObjectCompact { id: shape.id(), trait_object: shape.as_ref() };
+
+    let json = serde_json::to_string(&serialize).unwrap();
+    assert!(json.starts_with(&format!("{{\"{}\":", shape.id())), "{}", json);
+    let roundtrip = registry.deserialize_trait_object_compact(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+
+    let bytes = bincode::options().serialize(&serialize).unwrap();
+    assert!(bytes.windows(shape.id().len()).all(|window| window != shape.id().as_bytes()), "{:?}", bytes);
+    let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+    let roundtrip = registry.deserialize_trait_object_compact(&mut deserializer).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+  }
+}
+
+#[test]
+fn compact_id_is_stable() {
+  // 64-bit FNV-1a of the UTF-8 bytes of "Circle". Persisted data depends on this value, so it must never change.
+  assert_eq!(compact_id(Circle::ID), 0x14c54ca33d0f3ea9);
+  assert_eq!(compact_id(&String::from(Circle::ID)), compact_id(Circle::ID));
+  // Compact IDs hash the display form, so they are the same for IDs of other types with the same display form.
+  assert_eq!(compact_id(&7u32), compact_id("7"));
+}
+
+#[test]
+fn compact_non_string_ids() {
+  let mut registry = MapRegistry::<dyn Shape, u32>::new("Shape");
+  registry.register_type::<Circle>(1);
+  registry.register_type::<Label>(2);
+  let circle = Circle { radius: 6 };
+  let bytes = bincode::options().serialize(&SerializeTraitObjectCompact { id: 1u32, trait_object: &circle }).unwrap();
+  let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+  let deserialized = registry.deserialize_trait_object_compact(&mut deserializer).unwrap();
+  assert_eq!(deserialized.as_ref(), &circle as &dyn Shape);
+}
+
+#[test]
+fn compact_unknown_id() {
+  let registry = registry();
+  let bytes = bincode::options().serialize(&BTreeMap::from([(compact_id("Square"), Circle { radius: 3 })])).unwrap();
+  let mut deserializer = bincode::Deserializer::from_slice(&bytes, bincode::options());
+  let error = registry.deserialize_trait_object_compact(&mut deserializer).unwrap_err();
+  let expected = format!("no deserialize function was registered for id '{}'", compact_id("Square"));
+  assert!(error.to_string().contains(&expected), "{}", error);
+}
+
+#[test]
+fn untagged_roundtrip() {
+  let registry = registry();
+  for shape in shapes() {
+    let json = serialize_trait_object_untagged(serde_json::value::Serializer, shape.as_ref()).unwrap().to_string();
+    assert!(!json.contains(shape.id()), "{}", json);
+    let roundtrip = registry.deserialize_trait_object_untagged(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+
+    let mut bytes = Vec::new();
+    let mut serializer = rmp_serde::Serializer::new(&mut bytes).with_struct_map();
+    serialize_trait_object_untagged(&mut serializer, shape.as_ref()).unwrap();
+    let mut deserializer = rmp_serde::Deserializer::from_read_ref(&bytes);
+    let roundtrip = registry.deserialize_trait_object_untagged(&mut deserializer).unwrap();
+    assert_eq!(roundtrip.as_ref(), shape.as_ref());
+  }
+}
+
+#[test]
+fn untagged_candidates_in_order() {
+  let mut registry = registry();
+  /// Deserializes from the same data as `Circle`.
+  fn deserialize_as_label(
+    deserializer: &mut dyn erased_serde::Deserializer,
+  ) -> Result<Box<dyn Shape>, erased_serde::Error> {
+    let circle = Circle::deserialize(deserializer)?;
+    Ok(Box::new(Label { text: circle.radius.to_string(), color: Color([0, 0, 0]) }))
+  }
+  registry.register("CircleAsLabel", deserialize_as_label);
+
+  let candidates = [Circle::ID, "CircleAsLabel"];
+  let seed = DeserializeTraitObjectUntagged { registry: &registry, candidates: Some(&candidates) };
+  let deserialized = seed.deserialize(&mut serde_json::Deserializer::from_str(r#"{"radius":7}"#)).unwrap();
+  assert_eq!(deserialized.as_ref(), &Circle { radius: 7 } as &dyn Shape);
+
+  let candidates = ["CircleAsLabel", Circle::ID];
+  let seed = DeserializeTraitObjectUntagged { registry: &registry, candidates: Some(&candidates) };
+  let deserialized = seed.deserialize(&mut serde_json::Deserializer::from_str(r#"{"radius":7}"#)).unwrap();
+  let expected = Label { text: "7".to_string(), color: Color([0, 0, 0]) };
+  assert_eq!(deserialized.as_ref(), &expected as &dyn Shape);
+}
+
+#[test]
+fn untagged_no_match_reports_candidate_errors() {
+  let registry = registry();
+  let error = registry.deserialize_trait_object_untagged(&mut serde_json::Deserializer::from_str(r#"{"text":1}"#))
+    .unwrap_err()
+    .to_string();
+  assert!(error.contains("data did not match any concrete type of `Box<dyn Shape>`"), "{}", error);
+  assert!(error.contains(r#"id '"Circle"' failed with: missing field `radius`"#), "{}", error);
+  assert!(error.contains(r#"id '"Label"' failed with: invalid type: integer `1`, expected a string"#), "{}", error);
+}
+
+#[test]
+fn untagged_skips_duplicate_candidate() {
+  let mut registry = registry();
+  registry.register_type::<Circle>(Circle::ID);
+
+  // `Circle` has multiple registrations, so it is skipped and the next candidate is tried.
+  let json = r##"{"text":"D","color":"#000000"}"##;
+  let deserialized = registry.deserialize_trait_object_untagged(&mut serde_json::Deserializer::from_str(json)).unwrap();
+  let expected = Label { text: "D".to_string(), color: Color([0, 0, 0]) };
+  assert_eq!(deserialized.as_ref(), &expected as &dyn Shape);
+
+  let error = registry.deserialize_trait_object_untagged(&mut serde_json::Deserializer::from_str(r#"{"radius":1}"#))
+    .unwrap_err()
+    .to_string();
+  let expected = r#"id '"Circle"' was skipped: multiple deserialize functions were registered for id '"Circle"'"#;
+  assert!(error.contains(expected), "{}", error);
+}
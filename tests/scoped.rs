@@ -0,0 +1,112 @@
+//! Tests for scoped thread-local registries.
+
+mod common;
+
+use std::fmt::Debug;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use serde_flexitos::{serialize_trait_object, MapRegistry, Registry};
+use serde_flexitos::scoped::{with_registry, with_scoped_registry};
+use common::{registry, Circle, Color, Label, Shape};
+
+fn circle_registry() -> MapRegistry<dyn Shape> {
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  registry.register_type::<Circle>(Circle::ID);
+  registry
+}
+
+impl Serialize for dyn Shape {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object(serializer, self.id(), self)
+  }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Shape> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    with_scoped_registry(|registry: &MapRegistry<dyn Shape>| registry.deserialize_trait_object(deserializer))
+  }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+struct Drawing {
+  name: String,
+  shapes: Vec<Box<dyn Shape>>,
+}
+
+const LABEL: &str = r##"{"Label":{"text":"A","color":"#0080ff"}}"##;
+
+fn deserialize_shape(json: &str) -> Result<Box<dyn Shape>, serde_json::Error> {
+  serde_json::from_str(json)
+}
+
+
+#[test]
+fn scoped_derived() {
+  let registry = registry();
+  let drawing = Drawing {
+    name: "drawing".to_string(),
+    shapes: vec![
+      Box::new(Circle { radius: 2 }),
+      Box::new(Label { text: "A".to_string(), color: Color([0, 128, 255]) }),
+    ],
+  };
+  let json = serde_json::to_string(&drawing).unwrap();
+  let deserialized: Drawing = with_registry(&registry, || serde_json::from_str(&json)).unwrap();
+  assert_eq!(deserialized, drawing);
+}
+
+#[test]
+fn scoped_derived_bincode() {
+  let registry = registry();
+  let drawing = Drawing {
+    name: "drawing".to_string(),
+    shapes: vec![Box::new(Label { text: "A".to_string(), color: Color([0, 128, 255]) })],
+  };
+  let bytes = bincode::serialize(&drawing).unwrap();
+  let deserialized: Drawing = with_registry(&registry, || bincode::deserialize(&bytes)).unwrap();
+  assert_eq!(deserialized, drawing);
+}
+
+#[test]
+fn scoped_outside_any_scope() {
+  let error = deserialize_shape(r#"{"Circle":{"radius":2}}"#).unwrap_err();
+  assert!(error.to_string().contains("no registry of type"));
+  assert!(error.to_string().contains("MapRegistry"));
+}
+
+#[test]
+fn scoped_nested() {
+  let outer = circle_registry();
+  let inner = registry();
+  with_registry(&outer, || {
+    assert!(deserialize_shape(LABEL).is_err());
+    with_registry(&inner, || {
+      // The inner registry shadows the outer registry.
+      assert!(deserialize_shape(LABEL).is_ok());
+    });
+    // The outer registry is in scope again.
+    assert!(deserialize_shape(LABEL).is_err());
+    assert!(deserialize_shape(r#"{"Circle":{"radius":2}}"#).is_ok());
+  });
+  assert!(deserialize_shape(r#"{"Circle":{"radius":2}}"#).is_err());
+}
+
+#[test]
+fn scoped_restored_after_panic() {
+  let outer = circle_registry();
+  let inner = registry();
+  with_registry(&outer, || {
+    let result = catch_unwind(AssertUnwindSafe(|| with_registry(&inner, || panic!("deserialization panicked"))));
+    assert!(result.is_err());
+    // The outer registry is restored while unwinding out of the inner scope.
+    assert!(deserialize_shape(LABEL).is_err());
+    assert!(deserialize_shape(r#"{"Circle":{"radius":2}}"#).is_ok());
+  });
+
+  let result = catch_unwind(AssertUnwindSafe(|| with_registry(&inner, || panic!("deserialization panicked"))));
+  assert!(result.is_err());
+  // No registry is in scope after unwinding out of the outermost scope.
+  assert!(deserialize_shape(LABEL).is_err());
+}
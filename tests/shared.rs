@@ -0,0 +1,128 @@
+//! Tests for deserializing trait objects into shared pointers.
+
+mod common;
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+use serde::de::DeserializeSeed;
+use serde::{Serialize, Serializer};
+
+use serde_flexitos::{serialize_trait_object, MapRegistry, Registry};
+use serde_flexitos::de::{DeserializeTraitObjectArc, DeserializeTraitObjectRc};
+use serde_flexitos::shared::{DeserializeContext, SerializeContext, SerializeShared};
+use common::{registry, shapes, Circle, Color, Label, Shape};
+
+impl From<Circle> for Box<dyn Shape + Send + Sync> {
+  fn from(value: Circle) -> Self { Box::new(value) }
+}
+
+impl Serialize for dyn Shape {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serialize_trait_object(serializer, self.id(), self)
+  }
+}
+
+
+#[test]
+fn arc_seed_roundtrip() {
+  let registry = registry();
+  for shape in shapes() {
+    let json = serde_json::to_string(&shape).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let deserialized: Arc<dyn Shape> = DeserializeTraitObjectArc(&registry).deserialize(&mut deserializer).unwrap();
+    assert_eq!(deserialized.as_ref(), shape.as_ref());
+    assert_eq!(serde_json::to_string(deserialized.as_ref()).unwrap(), json);
+  }
+}
+
+#[test]
+fn rc_seed_roundtrip() {
+  let registry = registry();
+  for shape in shapes() {
+    let json = serde_json::to_string(&shape).unwrap();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    let deserialized: Rc<dyn Shape> = DeserializeTraitObjectRc(&registry).deserialize(&mut deserializer).unwrap();
+    assert_eq!(deserialized.as_ref(), shape.as_ref());
+    assert_eq!(serde_json::to_string(deserialized.as_ref()).unwrap(), json);
+  }
+}
+
+#[test]
+fn deserialize_trait_object_arc() {
+  let registry = registry();
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"Circle":{"radius":2}}"#);
+  let deserialized = registry.deserialize_trait_object_arc(&mut deserializer).unwrap();
+  assert_eq!(deserialized.as_ref(), &Circle { radius: 2 } as &dyn Shape);
+}
+
+#[test]
+fn deserialize_trait_object_arc_send_sync() {
+  let mut registry = MapRegistry::<dyn Shape + Send + Sync>::new("Shape");
+  registry.register_type::<Circle>(Circle::ID);
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"Circle":{"radius":2}}"#);
+  let deserialized: Arc<dyn Shape + Send + Sync> = registry.deserialize_trait_object_arc(&mut deserializer).unwrap();
+  let shared = Arc::clone(&deserialized);
+  let radius = std::thread::spawn(move || shared.as_any().downcast_ref::<Circle>().unwrap().radius).join().unwrap();
+  assert_eq!(radius, 2);
+}
+
+#[test]
+fn deserialize_trait_object_rc() {
+  let registry = registry();
+  let mut deserializer = serde_json::Deserializer::from_str(r##"{"Label":{"text":"A","color":"#0080ff"}}"##);
+  let deserialized = registry.deserialize_trait_object_rc(&mut deserializer).unwrap();
+  assert_eq!(deserialized.as_ref(), &Label { text: "A".to_string(), color: Color([0, 128, 255]) } as &dyn Shape);
+}
+
+#[test]
+fn deserialize_trait_object_arc_unknown_id() {
+  let registry = registry();
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"Square":{"side":3}}"#);
+  let error = registry.deserialize_trait_object_arc(&mut deserializer).unwrap_err();
+  assert!(error.to_string().contains("Square"), "{}", error);
+  let mut deserializer = serde_json::Deserializer::from_str(r#"{"Square":{"side":3}}"#);
+  assert!(registry.deserialize_trait_object_rc(&mut deserializer).is_err());
+}
+
+fn deserialize_shared(registry: &MapRegistry<dyn Shape>, json: &str) -> Result<Vec<Arc<dyn Shape>>, serde_json::Error> {
+  let context = DeserializeContext::new();
+  let values: Vec<serde_json::Value> = serde_json::from_str(json)?;
+  values.iter().map(|value| registry.deserialize_trait_object_shared(&context, value)).collect()
+}
+
+#[test]
+fn shared_roundtrip_preserves_sharing() {
+  let registry = registry();
+  let circle: Arc<dyn Shape> = Arc::new(Circle { radius: 1 });
+  let other_circle: Arc<dyn Shape> = Arc::new(Circle { radius: 1 });
+  let shapes = [circle.clone(), other_circle, circle];
+
+  let context = SerializeContext::new();
+  let serialize: Vec<_> = shapes.iter()
+    .map(|trait_object| SerializeShared { context: &context, trait_object })
+    .collect();
+  let json = serde_json::to_string(&serialize).unwrap();
+  assert_eq!(json, r#"[[0,{"Circle":{"radius":1}}],[1,{"Circle":{"radius":1}}],[0,null]]"#);
+
+  let roundtrip = deserialize_shared(&registry, &json).unwrap();
+  assert_eq!(roundtrip, shapes);
+  assert!(Arc::ptr_eq(&roundtrip[0], &roundtrip[2]));
+  assert!(!Arc::ptr_eq(&roundtrip[0], &roundtrip[1]));
+}
+
+#[test]
+fn shared_duplicate_reference() {
+  let registry = registry();
+  let json = r#"[[0,{"Circle":{"radius":1}}],[0,{"Circle":{"radius":2}}]]"#;
+  let error = deserialize_shared(&registry, json).unwrap_err();
+  assert!(error.to_string().contains("already deserialized"), "{}", error);
+}
+
+#[test]
+fn shared_missing_back_reference() {
+  let registry = registry();
+  let json = r#"[[1,null],[1,{"Circle":{"radius":1}}]]"#;
+  let error = deserialize_shared(&registry, json).unwrap_err();
+  assert!(error.to_string().contains("not deserialized before"), "{}", error);
+}
@@ -0,0 +1,103 @@
+//! Tests for `serde(with)` modules generated per registry.
+
+mod common;
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+
+use serde_flexitos::{serde_with_registry, MapRegistry, Registry};
+use common::{Circle, Color, Label, Shape};
+
+static ALL_REGISTRY: LazyLock<MapRegistry<dyn Shape>> = LazyLock::new(|| {
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  registry.register_type::<Circle>(Circle::ID);
+  registry.register_type::<Label>(Label::ID);
+  registry
+});
+static CIRCLE_REGISTRY: LazyLock<MapRegistry<dyn Shape>> = LazyLock::new(|| {
+  let mut registry = MapRegistry::<dyn Shape>::new("Shape");
+  registry.register_type::<Circle>(Circle::ID);
+  registry
+});
+
+serde_with_registry!(mod all for dyn Shape, registry: &*ALL_REGISTRY, id: Shape::id);
+serde_with_registry!(mod only_circle for dyn Shape, registry: &*CIRCLE_REGISTRY, id: Shape::id);
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Drawing {
+  #[serde(with = "all")]
+  single: Box<dyn Shape>,
+  #[serde(with = "all::vec")]
+  multiple: Vec<Box<dyn Shape>>,
+  #[serde(with = "only_circle::option", default)]
+  optional_circle: Option<Box<dyn Shape>>,
+  #[serde(with = "only_circle::map")]
+  circles: HashMap<String, Box<dyn Shape>>,
+}
+
+impl PartialEq for Drawing {
+  fn eq(&self, other: &Self) -> bool {
+    self.single.as_ref() == other.single.as_ref() && self.multiple == other.multiple
+      && self.optional_circle == other.optional_circle && self.circles == other.circles
+  }
+}
+
+fn drawing() -> Drawing {
+  Drawing {
+    single: Box::new(Label { text: "A".to_string(), color: Color([0, 128, 255]) }),
+    multiple: vec![Box::new(Circle { radius: 1 }), Box::new(Label { text: "B".to_string(), color: Color([0, 0, 0]) })],
+    optional_circle: Some(Box::new(Circle { radius: 2 })),
+    circles: HashMap::from([("c".to_string(), Box::new(Circle { radius: 3 }) as Box<dyn Shape>)]),
+  }
+}
+
+
+#[test]
+fn with_roundtrip() {
+  let drawing = drawing();
+  let json = serde_json::to_string(&drawing).unwrap();
+  let deserialized: Drawing = serde_json::from_str(&json).unwrap();
+  assert_eq!(deserialized, drawing);
+}
+
+#[test]
+fn with_roundtrip_bincode() {
+  let drawing = drawing();
+  let bytes = bincode::serialize(&drawing).unwrap();
+  let deserialized: Drawing = bincode::deserialize(&bytes).unwrap();
+  assert_eq!(deserialized, drawing);
+}
+
+#[test]
+fn with_option_none_and_missing() {
+  let drawing = Drawing { optional_circle: None, ..drawing() };
+  let json = serde_json::to_string(&drawing).unwrap();
+  assert!(json.contains(r#""optional_circle":null"#));
+  let deserialized: Drawing = serde_json::from_str(&json).unwrap();
+  assert_eq!(deserialized, drawing);
+
+  let json = r#"{"single":{"Circle":{"radius":1}},"multiple":[],"circles":{}}"#;
+  let deserialized: Drawing = serde_json::from_str(json).unwrap();
+  assert_eq!(deserialized.optional_circle, None);
+}
+
+#[test]
+fn with_uses_field_registry() {
+  // `Label` is registered in the registry used for `single`, but not in the registry used for `optional_circle`.
+  let json = r##"{"single":{"Label":{"text":"A","color":"#000000"}},"multiple":[],"circles":{}}"##;
+  assert!(serde_json::from_str::<Drawing>(json).is_ok());
+  let json = concat!(
+    r#"{"single":{"Circle":{"radius":1}},"multiple":[],"#,
+    r##""optional_circle":{"Label":{"text":"A","color":"#000000"}},"circles":{}}"##,
+  );
+  let error = serde_json::from_str::<Drawing>(json).unwrap_err();
+  assert!(error.to_string().contains("Label"));
+  let json = concat!(
+    r#"{"single":{"Circle":{"radius":1}},"multiple":[],"#,
+    r##""circles":{"c":{"Label":{"text":"A","color":"#000000"}}}}"##,
+  );
+  assert!(serde_json::from_str::<Drawing>(json).is_err());
+}